@@ -1,6 +1,11 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
 pub use anyhow::{Error as AnyError, Result};
+#[cfg(feature = "std")]
 use thiserror::Error;
 
+#[cfg(feature = "std")]
 #[derive(Error, Debug)]
 pub enum MCDMRSError {
     #[error("ERROR: {0}")]
@@ -8,3 +13,28 @@ pub enum MCDMRSError {
     // #[error(transparent)]
     // Other(#[from] anyhow::Error),
 }
+
+// Without `std` there is no `anyhow`, so errors are returned as `MCDMRSError` directly.
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+#[derive(Debug)]
+pub enum MCDMRSError {
+    Error(alloc::string::String),
+}
+
+#[cfg(not(feature = "std"))]
+impl core::fmt::Display for MCDMRSError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MCDMRSError::Error(e) => write!(f, "ERROR: {}", e),
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+pub type AnyError = MCDMRSError;
+
+#[cfg(not(feature = "std"))]
+pub type Result<T, E = AnyError> = core::result::Result<T, E>;
@@ -6,4 +6,6 @@ criterion_main! {
     benchmarks::mc_bench::benches,
     benchmarks::uc_bench::benches,
     benchmarks::from_file_bench::benches,
+    benchmarks::dominance_bench::benches,
+    benchmarks::simd_bench::benches,
 }
@@ -0,0 +1,31 @@
+use criterion::{criterion_group, Criterion};
+use mcdmrs_prom::dominance::pareto_front;
+use mcdmrs_prom::types::Fl;
+use mcdmrs_prom::ComputeOptions;
+use ndarray::{array, Array};
+use rand::{distributions::Uniform, Rng};
+
+pub fn dominance_bench(c: &mut Criterion) {
+    let mut rng = rand::thread_rng();
+    let range: Uniform<Fl> = Uniform::new(0.0, 1.0);
+    let m = 4;
+    let n = 20000;
+
+    let mat = Array::from_iter((0..(n * m)).map(|_| rng.sample(range)))
+        .into_shape((m, n))
+        .unwrap();
+    let criteria_type = array![1., -1., 1., -1.];
+
+    c.bench_function("pareto_n20000_m4", |b| {
+        b.iter(|| {
+            pareto_front(
+                mat.view(),
+                criteria_type.view(),
+                None,
+                &ComputeOptions::Global,
+            )
+        })
+    });
+}
+
+criterion_group! {name=benches; config = Criterion::default().sample_size(10); targets=dominance_bench}
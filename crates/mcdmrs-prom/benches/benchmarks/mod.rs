@@ -1,3 +1,5 @@
+pub mod dominance_bench;
 pub mod from_file_bench;
 pub mod mc_bench;
+pub mod simd_bench;
 pub mod uc_bench;
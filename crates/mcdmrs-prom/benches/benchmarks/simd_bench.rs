@@ -0,0 +1,62 @@
+use criterion::{criterion_group, Criterion};
+
+/// The scalar and the 8 lane preference function kernels on the same data, serially,
+/// so the speedup of the `simd` feature is measured without rayon. Run with
+/// `cargo bench --all-features -- simd`.
+#[cfg(feature = "simd")]
+pub fn simd_bench(c: &mut Criterion) {
+    use criterion::black_box;
+    use mcdmrs_prom::types::Fl;
+    use mcdmrs_prom::{simd, Level, PreferenceFunction, UShape, Usual, VShape, VShape2};
+    use rand::{distributions::Uniform, Rng};
+
+    fn scalar<F: PreferenceFunction>(values: &[Fl], q: Fl, p: Fl, f: F) -> (Fl, Fl) {
+        let (mut plus, mut minus) = (0.0, 0.0);
+        for v1 in values.iter() {
+            for v2 in values.iter() {
+                let diff = v1 - v2;
+                plus += f.preference(&diff, &q, &p);
+                minus += f.preference(&-diff, &q, &p);
+            }
+        }
+        (plus, minus)
+    }
+
+    let mut rng = rand::thread_rng();
+    let range: Uniform<Fl> = Uniform::new(0.0, 1.0);
+    let n = 4000;
+    let values: Vec<Fl> = (0..n).map(|_| 20.0 * rng.sample(range) - 10.0).collect();
+    let (q, p): (Fl, Fl) = (0.5, 2.0);
+
+    let mut group = c.benchmark_group("simd");
+    macro_rules! compare {
+        ($(($name:ident, $scalar:expr)),*) => {
+            $(
+                group.bench_function(concat!(stringify!($name), "_scalar"), |b| {
+                    b.iter(|| black_box(scalar(&values, q, p, $scalar)))
+                });
+                group.bench_function(concat!(stringify!($name), "_simd"), |b| {
+                    b.iter(|| {
+                        values.iter().fold((0.0, 0.0), |(plus, minus), &v1| {
+                            let (pl, mi) = simd::flow_sums(v1, &values, q, p, simd::$name);
+                            black_box((plus + pl, minus + mi))
+                        })
+                    })
+                });
+            )*
+        };
+    }
+    compare!(
+        (usual, Usual),
+        (ushape, UShape),
+        (vshape, VShape),
+        (vshape2, VShape2),
+        (level, Level)
+    );
+    group.finish();
+}
+
+#[cfg(not(feature = "simd"))]
+pub fn simd_bench(_c: &mut Criterion) {}
+
+criterion_group! {name=benches; config = Criterion::default().sample_size(10); targets=simd_bench}
@@ -1,9 +1,14 @@
 use criterion::{criterion_group, Criterion};
 use mcdmrs_prom::types::Fl;
-use mcdmrs_prom::unicriterion_flow::unicriterion_flow_usual;
-use ndarray::Array1;
+use mcdmrs_prom::unicriterion_flow::{
+    unicriterion_flow_level, unicriterion_flow_ushape, unicriterion_flow_usual,
+    unicriterion_flow_vshape, unicriterion_flow_vshape2,
+};
+use ndarray::{Array1, ArrayView1, ArrayViewMut1};
 use rand::{distributions::Uniform, Rng};
 
+type Kernel = fn(ArrayView1<Fl>, ArrayViewMut1<Fl>, ArrayViewMut1<Fl>, &Fl, &Fl);
+
 pub fn uc_bench(c: &mut Criterion) {
     let mut rng = rand::thread_rng();
     let range: Uniform<Fl> = Uniform::new(0.0, 1.0);
@@ -19,6 +24,21 @@ pub fn uc_bench(c: &mut Criterion) {
     c.bench_function("n8000", |b| {
         b.iter(|| unicriterion_flow_usual(array.view(), plus.view_mut(), minus.view_mut(), &q, &p))
     });
+
+    // every kernel, with thresholds that exercise all of their branches
+    let (q, p): (Fl, Fl) = (0.5, 2.0);
+    let kernels: [(&str, Kernel); 5] = [
+        ("usual", unicriterion_flow_usual),
+        ("ushape", unicriterion_flow_ushape),
+        ("vshape", unicriterion_flow_vshape),
+        ("vshape2", unicriterion_flow_vshape2),
+        ("level", unicriterion_flow_level),
+    ];
+    for (name, kernel) in kernels {
+        c.bench_function(&format!("n8000_{}", name), |b| {
+            b.iter(|| kernel(array.view(), plus.view_mut(), minus.view_mut(), &q, &p))
+        });
+    }
 }
 
 criterion_group! {name=benches; config = Criterion::default().sample_size(15); targets=uc_bench}
@@ -1,6 +1,6 @@
 use super::types::Fl;
 
-const REL_TOL: Fl = 1e-7;
+pub(crate) const REL_TOL: Fl = 1e-7;
 
 fn lhs(a: &Fl, b: &Fl) -> Fl {
     (a - b).abs()
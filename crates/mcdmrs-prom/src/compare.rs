@@ -0,0 +1,303 @@
+//! Similarity of two rankings, e.g., Promethee II against a re-weighted run or another
+//! method. Every function takes two score arrays where higher is better, such as net
+//! flows; pass rankings through [`scores_from_ranks`]. Scores within the crate
+//! tolerance are ties.
+use super::cmp::{gt, lt};
+use super::math::rank;
+use super::types::{Fl, MCDMRSError, Result};
+use ndarray::{Array1, ArrayView1};
+use std::cmp::Ordering;
+
+/// Every similarity measure between two rankings, see [`compare`].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Comparison {
+    pub spearman: Fl,
+    pub kendall_tau_b: Fl,
+    pub weighted_spearman: Fl,
+    pub ws: Fl,
+    pub top_k_overlap: Fl,
+    pub k: usize,
+}
+
+fn check(x: ArrayView1<Fl>, y: ArrayView1<Fl>) -> Result<()> {
+    if x.len() != y.len() || x.len() < 2 {
+        return Err(MCDMRSError::Error(format!(
+            "Rankings must have the same length of at least 2, got {} and {}",
+            x.len(),
+            y.len()
+        ))
+        .into());
+    }
+    if x.iter().chain(y.iter()).any(|v| v.is_nan()) {
+        return Err(MCDMRSError::Error("Rankings must not have missing values".to_string()).into());
+    }
+    Ok(())
+}
+
+fn sign(a: &Fl, b: &Fl) -> Ordering {
+    if gt(a, b) {
+        Ordering::Greater
+    } else if lt(a, b) {
+        Ordering::Less
+    } else {
+        Ordering::Equal
+    }
+}
+
+/// Turns ranks (1 is best) into scores for the functions of this module.
+pub fn scores_from_ranks(rank: ArrayView1<Fl>) -> Array1<Fl> {
+    rank.mapv(|r| -r)
+}
+
+/// Returns the 1-based descending rank of each score, with tied scores sharing the
+/// mean of their ranks (i.e., 1, 2.5, 2.5, 4).
+pub fn fractional_ranks(score: ArrayView1<Fl>) -> Array1<Fl> {
+    let mut order: Vec<usize> = (0..score.len()).collect();
+    order.sort_by(|&a, &b| score[b].total_cmp(&score[a]));
+
+    let mut ranks = Array1::<Fl>::zeros(score.len());
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && sign(&score[order[end - 1]], &score[order[end]]).is_eq() {
+            end += 1;
+        }
+        let mean = (start + end + 1) as Fl / 2.0;
+        order[start..end].iter().for_each(|&i| ranks[i] = mean);
+        start = end;
+    }
+    ranks
+}
+
+/// Spearman's rank correlation: the Pearson correlation of the fractional ranks. NaN
+/// when either ranking is all ties.
+pub fn spearman(x: ArrayView1<Fl>, y: ArrayView1<Fl>) -> Result<Fl> {
+    check(x, y)?;
+    let (rx, ry) = (fractional_ranks(x), fractional_ranks(y));
+    let (mx, my) = (rx.mean().unwrap_or(0.0), ry.mean().unwrap_or(0.0));
+    let (dx, dy) = (rx - mx, ry - my);
+    Ok((&dx * &dy).sum() / ((&dx * &dx).sum() * (&dy * &dy).sum()).sqrt())
+}
+
+/// Kendall's tau-b, which corrects for ties in either ranking. NaN when either
+/// ranking is all ties.
+///
+/// Ties are the groups of [`fractional_ranks`], and the pairs are counted with
+/// Knight's O(n log n) algorithm.
+pub fn kendall_tau_b(x: ArrayView1<Fl>, y: ArrayView1<Fl>) -> Result<Fl> {
+    check(x, y)?;
+    let n = x.len();
+    // the ranks number the tie groups
+    let (rx, ry) = (rank(x), rank(y));
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_unstable_by_key(|&i| (rx[i], ry[i]));
+
+    let x_ties = tied_pairs(&order.iter().map(|&i| rx[i]).collect::<Vec<_>>());
+    let joint_ties = tied_pairs(&order.iter().map(|&i| (rx[i], ry[i])).collect::<Vec<_>>());
+    let mut by_y: Vec<usize> = order.iter().map(|&i| ry[i]).collect();
+    let discordant = merge_sort(&mut by_y);
+    let y_ties = tied_pairs(&by_y);
+
+    let pairs = (n * (n - 1) / 2) as f64;
+    let (x_ties, y_ties) = (x_ties as f64, y_ties as f64);
+    let numerator = pairs - x_ties - y_ties + joint_ties as f64 - 2.0 * discordant as f64;
+    let denominator = ((pairs - x_ties) * (pairs - y_ties)).sqrt();
+    Ok((numerator / denominator) as Fl)
+}
+
+/// The number of pairs within the runs of equal values of `v`.
+fn tied_pairs<T: PartialEq>(v: &[T]) -> u64 {
+    v.chunk_by(|a, b| a == b)
+        .map(|run| (run.len() * (run.len() - 1) / 2) as u64)
+        .sum()
+}
+
+/// Stably sorts `v` in increasing order, returning the number of pairs it swaps.
+fn merge_sort(v: &mut [usize]) -> u64 {
+    if v.len() < 2 {
+        return 0;
+    }
+    let mid = v.len() / 2;
+    let mut swaps = merge_sort(&mut v[..mid]) + merge_sort(&mut v[mid..]);
+
+    let mut merged = Vec::with_capacity(v.len());
+    let (mut i, mut j) = (0, mid);
+    while i < mid && j < v.len() {
+        if v[j] < v[i] {
+            swaps += (mid - i) as u64;
+            merged.push(v[j]);
+            j += 1;
+        } else {
+            merged.push(v[i]);
+            i += 1;
+        }
+    }
+    merged.extend_from_slice(&v[i..mid]);
+    merged.extend_from_slice(&v[j..]);
+    v.copy_from_slice(&merged);
+    swaps
+}
+
+/// The weighted Spearman coefficient `r_w`, which weighs differences near the top of
+/// the rankings more.
+pub fn weighted_spearman(x: ArrayView1<Fl>, y: ArrayView1<Fl>) -> Result<Fl> {
+    check(x, y)?;
+    let n = x.len() as Fl;
+    let (rx, ry) = (fractional_ranks(x), fractional_ranks(y));
+    let sum: Fl = rx
+        .iter()
+        .zip(ry.iter())
+        .map(|(a, b)| (a - b).powi(2) * ((n - a + 1.0) + (n - b + 1.0)))
+        .sum();
+    Ok(1.0 - 6.0 * sum / (n.powi(4) + n.powi(3) - n.powi(2) - n))
+}
+
+/// The WS similarity coefficient of Sałabun and Urbaniak, which depends mostly on the
+/// top of the reference ranking `x`, so it is not symmetric.
+pub fn ws_coefficient(x: ArrayView1<Fl>, y: ArrayView1<Fl>) -> Result<Fl> {
+    check(x, y)?;
+    let n = x.len() as Fl;
+    let (rx, ry) = (fractional_ranks(x), fractional_ranks(y));
+    let sum: Fl = rx
+        .iter()
+        .zip(ry.iter())
+        .map(|(a, b)| (2.0 as Fl).powf(-a) * (a - b).abs() / (a - 1.0).abs().max((a - n).abs()))
+        .sum();
+    Ok(1.0 - sum)
+}
+
+/// The share of the best `k` alternatives of `x` that are also among the best `k` of
+/// `y`. Alternatives tied at rank `k` are all included, so the sets may be larger
+/// than `k` and the shared count is divided by the larger set.
+pub fn top_k_overlap(x: ArrayView1<Fl>, y: ArrayView1<Fl>, k: usize) -> Result<Fl> {
+    check(x, y)?;
+    if k == 0 {
+        return Err(MCDMRSError::Error("`k` must be at least 1".to_string()).into());
+    }
+    let (rx, ry) = (rank(x), rank(y));
+    let (a, b) = (
+        rx.iter().filter(|&&r| r <= k).count(),
+        ry.iter().filter(|&&r| r <= k).count(),
+    );
+    let shared = rx
+        .iter()
+        .zip(ry.iter())
+        .filter(|(&i, &j)| i <= k && j <= k)
+        .count();
+    Ok(shared as Fl / a.max(b) as Fl)
+}
+
+/// Computes every similarity measure between the rankings, with `x` as the reference.
+pub fn compare(x: ArrayView1<Fl>, y: ArrayView1<Fl>, k: usize) -> Result<Comparison> {
+    Ok(Comparison {
+        spearman: spearman(x, y)?,
+        kendall_tau_b: kendall_tau_b(x, y)?,
+        weighted_spearman: weighted_spearman(x, y)?,
+        ws: ws_coefficient(x, y)?,
+        top_k_overlap: top_k_overlap(x, y, k)?,
+        k,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use is_close::all_close;
+    use ndarray::array;
+
+    #[test]
+    fn test_fractional_ranks() {
+        let score = array![0.3, 0.5, 0.3 + 1e-9, 0.1];
+        assert_eq!(array![2.5, 1., 2.5, 4.], fractional_ranks(score.view()));
+    }
+
+    #[test]
+    fn test_compare() -> Result<()> {
+        let x = scores_from_ranks(array![1., 2., 3., 4., 5.].view());
+        let same = compare(x.view(), x.view(), 2)?;
+        assert_eq!(
+            Comparison {
+                spearman: 1.,
+                kendall_tau_b: 1.,
+                weighted_spearman: 1.,
+                ws: 1.,
+                top_k_overlap: 1.,
+                k: 2
+            },
+            same
+        );
+
+        let reversed = compare(x.view(), (-&x).view(), 2)?;
+        assert!(all_close!(
+            vec![-1., -1., -1., 0.260417, 0.],
+            vec![
+                reversed.spearman,
+                reversed.kendall_tau_b,
+                reversed.weighted_spearman,
+                reversed.ws,
+                reversed.top_k_overlap
+            ],
+            abs_tol = 1e-5
+        ));
+
+        // swapping the best two matters more than swapping the worst two
+        let y = scores_from_ranks(array![2., 1., 3., 4., 5.].view());
+        let z = scores_from_ranks(array![1., 2., 3., 5., 4.].view());
+        assert!((weighted_spearman(x.view(), y.view())? - 0.85).abs() < 1e-5);
+        assert!((ws_coefficient(x.view(), y.view())? - 0.791667).abs() < 1e-5);
+        assert!(weighted_spearman(x.view(), z.view())? > 0.85);
+        assert!(ws_coefficient(x.view(), z.view())? > 0.791667);
+        assert_eq!(spearman(x.view(), y.view())?, spearman(x.view(), z.view())?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ties() -> Result<()> {
+        // the same values as scipy.stats
+        let x = array![1., 2., 2., 3.];
+        let y = array![1., 2., 3., 4.];
+        assert!((spearman(x.view(), y.view())? - 0.948683).abs() < 1e-5);
+        assert!((kendall_tau_b(x.view(), y.view())? - 0.912871).abs() < 1e-5);
+        // the 2s of `x` tie for rank 2, so its top 2 holds 3 alternatives
+        assert!((top_k_overlap(x.view(), y.view(), 2)? - 2. / 3.).abs() < 1e-6);
+        assert!(spearman(x.view(), array![1., 1., 1., 1.].view())?.is_nan());
+
+        assert!(spearman(x.view(), array![1., 2.].view()).is_err());
+        assert!(spearman(x.view(), array![1., 2., Fl::NAN, 3.].view()).is_err());
+        assert!(top_k_overlap(x.view(), y.view(), 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_chained_ties() -> Result<()> {
+        // a ties b and b ties c within the tolerance, but a and c are apart
+        let a: Fl = 1.9;
+        let b = Fl::from_bits(a.to_bits() + 1);
+        let c = Fl::from_bits(b.to_bits() + 1);
+        assert!(lt(&a, &c));
+        let x = array![a, b, c, 3.];
+        let y = array![1., 2., 3., 4.];
+        let grouped = array![2., 2., 2., 3.];
+        assert_eq!(fractional_ranks(grouped.view()), fractional_ranks(x.view()));
+        assert_eq!(
+            kendall_tau_b(grouped.view(), y.view())?,
+            kendall_tau_b(x.view(), y.view())?
+        );
+        // 3 of the 6 pairs are untied in `x`, so tau-b is 3 / sqrt(3 * 6)
+        assert!(
+            (kendall_tau_b(x.view(), y.view())? - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-5
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_kendall_tau_b() -> Result<()> {
+        // the same value as counting every pair
+        let x = array![3., 1., 4., 1., 5., 9., 2., 6., 5., 3.];
+        let y = array![2., 7., 1., 8., 2., 8., 1., 8., 2., 8.];
+        assert!((kendall_tau_b(x.view(), y.view())? - 0.130410).abs() < 1e-5);
+        assert!((kendall_tau_b(y.view(), x.view())? - 0.130410).abs() < 1e-5);
+        Ok(())
+    }
+}
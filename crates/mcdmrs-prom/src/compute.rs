@@ -0,0 +1,140 @@
+#[cfg(feature = "parallel")]
+use super::types::{MCDMRSError, Result};
+#[cfg(feature = "parallel")]
+use alloc::{format, sync::Arc};
+#[cfg(feature = "parallel")]
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+/// Where the parallel kernels run.
+///
+/// Every kernel computes each output value in a single task with a fixed summation
+/// order, so results are bitwise identical whatever the number of threads, including
+/// [`ComputeOptions::Serial`].
+///
+/// Without the `parallel` feature every option runs serially.
+#[derive(Clone, Debug, Default)]
+pub enum ComputeOptions {
+    /// The rayon global thread pool.
+    #[default]
+    Global,
+    /// A caller-owned thread pool, for example to keep a web service's request
+    /// handlers responsive.
+    #[cfg(feature = "parallel")]
+    Pool(Arc<ThreadPool>),
+    /// The calling thread only, without rayon. For embedded and single-threaded wasm
+    /// builds.
+    Serial,
+}
+
+impl ComputeOptions {
+    /// Returns options running on a new pool of `threads` threads.
+    #[cfg(feature = "parallel")]
+    pub fn threads(threads: usize) -> Result<ComputeOptions> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .map_err(|e| MCDMRSError::Error(format!("Cannot build a thread pool: {}", e)))?;
+        Ok(ComputeOptions::Pool(Arc::new(pool)))
+    }
+
+    pub fn is_serial(&self) -> bool {
+        !cfg!(feature = "parallel") || matches!(self, ComputeOptions::Serial)
+    }
+
+    /// Runs `op` in the configured pool, so the parallel iterators it calls use it.
+    pub fn install<R, OP>(&self, op: OP) -> R
+    where
+        OP: FnOnce() -> R + Send,
+        R: Send,
+    {
+        match self {
+            #[cfg(feature = "parallel")]
+            ComputeOptions::Pool(pool) => pool.install(op),
+            _ => op(),
+        }
+    }
+}
+
+/// Runs `zip.par_for_each(f)`, or `zip.for_each(f)` when `options` is serial.
+#[cfg(feature = "parallel")]
+macro_rules! zip_for_each {
+    ($options:expr, $zip:expr, $f:expr) => {{
+        let f = $f;
+        if $options.is_serial() {
+            $zip.for_each(f)
+        } else {
+            $options.install(|| $zip.par_for_each(f))
+        }
+    }};
+}
+
+/// Runs `zip.for_each(f)`.
+#[cfg(not(feature = "parallel"))]
+macro_rules! zip_for_each {
+    ($options:expr, $zip:expr, $f:expr) => {{
+        let _: &$crate::compute::ComputeOptions = $options;
+        $zip.for_each($f)
+    }};
+}
+
+pub(crate) use zip_for_each;
+
+#[cfg(all(test, feature = "parallel"))]
+mod test {
+    use super::super::promethee::{Criteria, Prom};
+    use super::super::types::Fl;
+    use super::super::utils::generate_prom;
+    use super::*;
+    use ndarray::Array1;
+
+    fn bits(p: &Prom) -> Vec<u32> {
+        p.prom_ii
+            .as_ref()
+            .unwrap()
+            .score
+            .iter()
+            .map(|v| v.to_bits())
+            .collect()
+    }
+
+    #[test]
+    fn test_deterministic() -> Result<()> {
+        let mut p = generate_prom(500, 5)?;
+        let len = 5;
+        p.criteria = Criteria::new(
+            Array1::from(vec![1.; len]),
+            Array1::from(vec![1., -1., 1., -1., 1.]),
+            Array1::from_iter(["usual", "ushape", "vshape", "vshape2", "level"].map(String::from)),
+            Array1::<Fl>::from(vec![1.; len]),
+            Array1::<Fl>::from(vec![4.; len]),
+        )?;
+        p.matrix_t[[2, 7]] = Fl::NAN;
+
+        let mut results = vec![];
+        for options in [
+            ComputeOptions::Global,
+            ComputeOptions::Serial,
+            ComputeOptions::threads(1)?,
+            ComputeOptions::threads(3)?,
+        ] {
+            let mut p = p.clone().with_options(options);
+            p.compute_prom_ii()?;
+            results.push(bits(&p));
+        }
+        assert!(results.iter().all(|r| *r == results[0]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_threads() {
+        assert!(ComputeOptions::threads(2).is_ok());
+        assert!(ComputeOptions::Serial.is_serial());
+        assert_eq!(
+            4,
+            ComputeOptions::threads(4)
+                .unwrap()
+                .install(rayon::current_num_threads)
+        );
+    }
+}
@@ -0,0 +1,458 @@
+//! This module makes it possible to describe an analysis in a single TOML project file.
+//!
+//! ```toml
+//! [data]
+//! path = "alternatives.csv"  # relative to the project file, any format read by `df_from_file`
+//! id_column = "site_id"
+//! missing = "valid"  # skip, valid, mean, median, worst, best or exclude
+//!
+//! [[criteria]]
+//! name = "cost"
+//! weight = 2.0
+//! direction = "min"
+//! function = "linear"
+//! q = 20000.0
+//! p = 100000.0
+//!
+//! [[criteria]]
+//! name = "risk"
+//! direction = "min"
+//! scale = ["low", "medium", "high"]
+//! scale_values = [0.0, 1.0, 5.0]  # optional, defaults to 0, 1, 2, ...
+//!
+//! [filter]
+//! exclude = ["7", "12"]
+//!
+//! [[filter.range]]
+//! column = "site_slope"
+//! max = 0.1
+//!
+//! [output]
+//! path = "results.csv"
+//! include_flows = true
+//! ```
+
+use super::interop::files::{df_from_file, CsvOptions};
+use super::interop::polars::{df_to_labels, prom_from_polars_with_criteria};
+use super::missing::MissingPolicy;
+use super::scale::OrdinalScale;
+use super::types::{Fl, MCDMRSError, Result};
+use super::validation::validate_criteria;
+use super::{Criteria, Prom};
+use ndarray::Array1;
+use polars::prelude::{BooleanChunked, DataFrame, DataType, NamedFrom, Series};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    #[default]
+    Max,
+    Min,
+}
+
+impl Direction {
+    pub fn criteria_type(&self) -> Fl {
+        match self {
+            Direction::Max => 1.0,
+            Direction::Min => -1.0,
+        }
+    }
+}
+
+fn default_weight() -> Fl {
+    1.0
+}
+
+fn default_function() -> String {
+    "usual".to_string()
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(deny_unknown_fields)]
+pub struct DataConfig {
+    pub path: PathBuf,
+    pub id_column: Option<String>,
+    /// How missing values are handled, see [`MissingPolicy`]. Defaults to `skip`.
+    pub missing: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(deny_unknown_fields)]
+pub struct CriterionConfig {
+    pub name: String,
+    #[serde(default = "default_weight")]
+    pub weight: Fl,
+    #[serde(default)]
+    pub direction: Direction,
+    #[serde(default = "default_function")]
+    pub function: String,
+    #[serde(default)]
+    pub q: Fl,
+    #[serde(default)]
+    pub p: Fl,
+    /// The ordered labels of a qualitative criterion.
+    pub scale: Option<Vec<String>>,
+    /// The value of each label in `scale`.
+    pub scale_values: Option<Vec<Fl>>,
+}
+
+impl CriterionConfig {
+    pub fn ordinal_scale(&self) -> Result<Option<OrdinalScale>> {
+        match (&self.scale, &self.scale_values) {
+            (Some(labels), values) => Ok(Some(OrdinalScale::new(labels.clone(), values.clone())?)),
+            (None, Some(_)) => Err(MCDMRSError::Error(format!(
+                "criterion `{}` declares `scale_values` without a `scale`",
+                self.name
+            ))
+            .into()),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(deny_unknown_fields)]
+pub struct RangeFilter {
+    pub column: String,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(deny_unknown_fields)]
+pub struct FilterConfig {
+    /// Only keep alternatives with these ids. Requires `data.id_column`.
+    pub include: Option<Vec<String>>,
+    /// Drop alternatives with these ids. Requires `data.id_column`.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Only keep alternatives whose value in `column` lies within `[min, max]`.
+    #[serde(default)]
+    pub range: Vec<RangeFilter>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(deny_unknown_fields)]
+pub struct OutputConfig {
+    pub path: Option<PathBuf>,
+    pub format: Option<String>,
+    #[serde(default)]
+    pub include_flows: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    pub data: DataConfig,
+    pub criteria: Vec<CriterionConfig>,
+    #[serde(default)]
+    pub filter: FilterConfig,
+    #[serde(default)]
+    pub output: OutputConfig,
+    /// The directory relative paths are resolved against.
+    #[serde(skip)]
+    pub base_dir: PathBuf,
+}
+
+impl ProjectConfig {
+    pub fn from_toml_str(s: &str) -> Result<ProjectConfig> {
+        let config: ProjectConfig = toml::from_str(s)?;
+        if config.criteria.is_empty() {
+            return Err(MCDMRSError::Error(
+                "Project must declare at least one criterion".to_string(),
+            )
+            .into());
+        }
+        Ok(config)
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<ProjectConfig> {
+        let path = path.as_ref();
+        let mut config = Self::from_toml_str(&std::fs::read_to_string(path)?)?;
+        config.base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(config)
+    }
+
+    /// Resolves `path` against the directory of the project file.
+    pub fn resolve(&self, path: &Path) -> PathBuf {
+        if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.base_dir.join(path)
+        }
+    }
+
+    pub fn missing_policy(&self) -> Result<MissingPolicy> {
+        match &self.data.missing {
+            Some(s) => Ok(s.parse()?),
+            None => Ok(MissingPolicy::default()),
+        }
+    }
+
+    pub fn criteria_names(&self) -> Vec<String> {
+        self.criteria.iter().map(|c| c.name.clone()).collect()
+    }
+
+    /// Returns the criteria of the project.
+    pub fn criteria(&self) -> Result<Criteria> {
+        let c = &self.criteria;
+        Criteria::new(
+            c.iter().map(|c| c.weight).collect(),
+            c.iter().map(|c| c.direction.criteria_type()).collect(),
+            c.iter().map(|c| c.function.clone()).collect(),
+            c.iter().map(|c| c.q).collect(),
+            c.iter().map(|c| c.p).collect(),
+        )?
+        .with_names(Array1::from_vec(self.criteria_names()))?
+        .with_scales(
+            c.iter()
+                .map(CriterionConfig::ordinal_scale)
+                .collect::<Result<Array1<Option<OrdinalScale>>>>()?,
+        )
+    }
+
+    /// Returns the criteria in the same layout as a criteria csv file.
+    pub fn criteria_df(&self) -> Result<DataFrame> {
+        let c = &self.criteria;
+        let scales = c
+            .iter()
+            .map(|c| Ok(c.ordinal_scale()?.map(|s| s.to_string())))
+            .collect::<Result<Vec<Option<String>>>>()?;
+
+        let mut df = DataFrame::new(vec![
+            Series::new("name", self.criteria_names()),
+            Series::new("weight", c.iter().map(|c| c.weight).collect::<Vec<Fl>>()),
+            Series::new(
+                "criteria_type",
+                c.iter()
+                    .map(|c| c.direction.criteria_type())
+                    .collect::<Vec<Fl>>(),
+            ),
+            Series::new(
+                "pref_function",
+                c.iter()
+                    .map(|c| c.function.clone())
+                    .collect::<Vec<String>>(),
+            ),
+            Series::new("q", c.iter().map(|c| c.q).collect::<Vec<Fl>>()),
+            Series::new("p", c.iter().map(|c| c.p).collect::<Vec<Fl>>()),
+        ])?;
+        if scales.iter().any(Option::is_some) {
+            df.with_column(Series::new("scale", scales))?;
+        }
+        Ok(df)
+    }
+
+    fn id_mask(&self, df: &DataFrame) -> Result<Option<Vec<bool>>> {
+        let f = &self.filter;
+        if f.include.is_none() && f.exclude.is_empty() {
+            return Ok(None);
+        }
+        let id_column = self.data.id_column.as_ref().ok_or(MCDMRSError::Error(
+            "`filter.include` and `filter.exclude` require `data.id_column`".to_string(),
+        ))?;
+
+        let ids = df.column(id_column)?.cast(&DataType::String)?;
+        let mask = ids
+            .str()?
+            .into_iter()
+            .map(|id| {
+                let id = id.unwrap_or_default().to_string();
+                let included = f.include.as_ref().is_none_or(|inc| inc.contains(&id));
+                included && !f.exclude.contains(&id)
+            })
+            .collect();
+        Ok(Some(mask))
+    }
+
+    fn range_mask(&self, df: &DataFrame, range: &RangeFilter) -> Result<Vec<bool>> {
+        let values = df.column(&range.column)?.cast(&DataType::Float64)?;
+        let mask = values
+            .f64()?
+            .into_iter()
+            .map(|v| match v {
+                Some(v) => {
+                    range.min.is_none_or(|min| v >= min) && range.max.is_none_or(|max| v <= max)
+                }
+                None => false,
+            })
+            .collect();
+        Ok(mask)
+    }
+
+    /// Loads the alternatives and applies the project filters.
+    pub fn load_data(&self) -> Result<DataFrame> {
+        let path = self.resolve(&self.data.path);
+        let df = df_from_file(&path, &CsvOptions::default())?;
+
+        let mut mask = vec![true; df.height()];
+        let masks = self.id_mask(&df)?.into_iter().chain(
+            self.filter
+                .range
+                .iter()
+                .map(|r| self.range_mask(&df, r))
+                .collect::<Result<Vec<_>>>()?,
+        );
+        for m in masks {
+            mask.iter_mut().zip(m).for_each(|(a, b)| *a &= b);
+        }
+
+        Ok(df.filter(&mask.into_iter().collect::<BooleanChunked>())?)
+    }
+}
+
+pub trait FromConfig {
+    fn from_config(config: &ProjectConfig) -> Result<Prom>;
+    /// Same as [`FromConfig::from_config`] with the data already loaded by
+    /// [`ProjectConfig::load_data`].
+    fn from_config_with_data(config: &ProjectConfig, data_df: &DataFrame) -> Result<Prom>;
+}
+
+impl FromConfig for Prom {
+    fn from_config(config: &ProjectConfig) -> Result<Prom> {
+        Prom::from_config_with_data(config, &config.load_data()?)
+    }
+
+    fn from_config_with_data(config: &ProjectConfig, data_df: &DataFrame) -> Result<Prom> {
+        validate_criteria(&config.criteria_df()?, Some(&data_df.get_column_names()))
+            .into_result()?;
+        let p = prom_from_polars_with_criteria(data_df, config.criteria()?)?;
+        let p = match &config.data.id_column {
+            Some(id) => p.with_labels(df_to_labels(data_df, id)?)?,
+            None => p,
+        };
+        Ok(p.with_missing_policy(config.missing_policy()?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PROJECT: &str = r#"
+        [data]
+        path = "../../examples/data/alternatives.csv"
+        id_column = ""
+
+        [[criteria]]
+        name = "cost"
+        weight = 2.0
+        direction = "min"
+        function = "linear"
+        q = 20000.0
+        p = 100000.0
+
+        [[criteria]]
+        name = "treated_area"
+    "#;
+
+    #[test]
+    fn test_from_config() -> Result<()> {
+        let config = ProjectConfig::from_toml_str(PROJECT)?;
+        assert_eq!(Direction::Max, config.criteria[1].direction);
+        assert_eq!("usual", config.criteria[1].function);
+
+        let mut p = Prom::from_config(&config)?;
+        p.compute_prom_ii()?;
+
+        assert_eq!((2, 10), p.matrix_t.dim());
+        assert_eq!(Some("3"), p.labels.as_ref().map(|l| l[3].as_str()));
+        assert_eq!(-1.0, p.criteria.criteria_type[0]);
+        assert_eq!(1.0, p.criteria.weight[1]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_filters() -> Result<()> {
+        let mut config = ProjectConfig::from_toml_str(PROJECT)?;
+        config.filter.exclude = vec!["0".to_string(), "1".to_string()];
+        assert_eq!(8, config.load_data()?.height());
+
+        config.filter.include = Some(vec!["1".to_string(), "2".to_string(), "3".to_string()]);
+        assert_eq!(2, config.load_data()?.height());
+
+        config.filter = FilterConfig {
+            range: vec![RangeFilter {
+                column: "treated_area".to_string(),
+                min: Some(100.0),
+                max: None,
+            }],
+            ..Default::default()
+        };
+        let df = config.load_data()?;
+        assert!(df
+            .column("treated_area")?
+            .f64()?
+            .into_iter()
+            .all(|v| v.unwrap() >= 100.0));
+
+        config.data.id_column = None;
+        config.filter.exclude = vec!["0".to_string()];
+        assert!(config.load_data().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_errors() {
+        assert!(ProjectConfig::from_toml_str("criteria = []\n[data]\npath = 'a.csv'").is_err());
+        assert!(ProjectConfig::from_toml_str(
+            "criteria = []\n[data]\npath = 'a.csv'\nunknown_key = 1"
+        )
+        .is_err());
+        let bad_direction = PROJECT.replace("\"min\"", "\"down\"");
+        assert!(ProjectConfig::from_toml_str(&bad_direction).is_err());
+        let bad_missing = PROJECT.replace("id_column = \"\"", "missing = \"zero\"");
+        let config = ProjectConfig::from_toml_str(&bad_missing).unwrap();
+        assert!(Prom::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_ordinal_scale() -> Result<()> {
+        let mut config = ProjectConfig::from_toml_str(PROJECT)?;
+        config.criteria[1].scale = Some(vec!["small".to_string(), "large".to_string()]);
+        config.criteria[1].scale_values = Some(vec![1.0, 10.0]);
+        let df = config.criteria_df()?;
+        let scale: Vec<Option<&str>> = df.column("scale")?.str()?.into_iter().collect();
+        assert_eq!(vec![None, Some("small=1|large=10")], scale);
+
+        let scales = config.criteria()?.scale.unwrap();
+        assert_eq!(None, scales[0]);
+        assert_eq!(
+            Some(OrdinalScale::new(
+                vec!["small".to_string(), "large".to_string()],
+                Some(vec![1.0, 10.0])
+            )?),
+            scales[1]
+        );
+
+        // treated_area is numeric, so none of its values are known labels
+        assert!(Prom::from_config(&config).is_err());
+
+        config.criteria[1].scale = None;
+        assert!(config.criteria_df().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_path() -> Result<()> {
+        let config = ProjectConfig::from_path("../../examples/data/project.toml")?;
+        assert_eq!(Path::new("../../examples/data"), config.base_dir);
+
+        let p = Prom::from_config(&config)?;
+        assert_eq!(config.criteria.len(), p.matrix_t.dim().0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,288 @@
+use super::compute::{zip_for_each, ComputeOptions};
+use super::promethee::Prom;
+use super::types::{Fl, MCDMRSError, Result};
+use ndarray::{Array1, ArrayView1, ArrayView2, Axis, Zip};
+
+/// Returns true when `a` is not worse than `b` on any criterion and better on at least
+/// one, beyond the tolerances. A missing value on either side prevents dominance.
+pub fn dominates(
+    matrix_t: ArrayView2<Fl>,
+    criteria_type: ArrayView1<Fl>,
+    tolerance: Option<ArrayView1<Fl>>,
+    a: usize,
+    b: usize,
+) -> bool {
+    let mut better = false;
+    for (j, row) in matrix_t.axis_iter(Axis(0)).enumerate() {
+        let diff = (row[a] - row[b]) * criteria_type[j];
+        let eps = tolerance.map_or(0.0, |t| t[j]);
+        if diff.is_nan() || diff < -eps {
+            return false;
+        }
+        better |= diff > eps;
+    }
+    better
+}
+
+fn check_dims(
+    matrix_t: ArrayView2<Fl>,
+    criteria_type: ArrayView1<Fl>,
+    tolerance: Option<ArrayView1<Fl>>,
+) -> Result<()> {
+    let m = matrix_t.dim().0;
+    if m != criteria_type.len() || tolerance.is_some_and(|t| t.len() != m) {
+        return Err(MCDMRSError::Error(
+            "The 0 dimension of `matrix_t` must be of same length as `criteria_type` and the \
+             tolerances"
+                .to_string(),
+        )
+        .into());
+    }
+    if tolerance.is_some_and(|t| t.iter().any(|v| v.is_nan() || *v < 0.0)) {
+        return Err(MCDMRSError::Error("Tolerances must be non-negative".to_string()).into());
+    }
+    Ok(())
+}
+
+/// Flags the alternatives of `candidates` that no other candidate dominates.
+fn non_dominated(
+    matrix_t: ArrayView2<Fl>,
+    criteria_type: ArrayView1<Fl>,
+    tolerance: Option<ArrayView1<Fl>>,
+    candidates: &[usize],
+    options: &ComputeOptions,
+) -> Array1<bool> {
+    let eps_sum = tolerance.map_or(0.0, |t| t.sum());
+    let oriented_sum = |i: usize| -> Fl {
+        matrix_t
+            .column(i)
+            .iter()
+            .zip(criteria_type.iter())
+            .map(|(v, t)| v * t)
+            .sum()
+    };
+
+    // only the candidates with a larger sum, within the tolerances, can dominate
+    let mut sorted: Vec<(Fl, usize)> = candidates
+        .iter()
+        .map(|&i| (oriented_sum(i), i))
+        .filter(|(s, _)| !s.is_nan())
+        .collect();
+    sorted.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut front = Array1::from_elem(candidates.len(), true);
+    zip_for_each!(
+        options,
+        Zip::from(&mut front).and(&ArrayView1::from(candidates)),
+        |keep: &mut bool, &i: &usize| {
+            let s = oriented_sum(i);
+            if s.is_nan() {
+                return;
+            }
+            let bound = s - eps_sum;
+            let bound = bound - bound.abs().max(1.0) * 1e-5;
+            let end = sorted.partition_point(|(other, _)| *other >= bound);
+            *keep = !sorted[..end]
+                .iter()
+                .any(|&(_, j)| j != i && dominates(matrix_t, criteria_type, tolerance, j, i));
+        }
+    );
+    front
+}
+
+/// Returns the indices of the Pareto-optimal alternatives, in increasing order.
+pub fn pareto_front(
+    matrix_t: ArrayView2<Fl>,
+    criteria_type: ArrayView1<Fl>,
+    tolerance: Option<ArrayView1<Fl>>,
+    options: &ComputeOptions,
+) -> Result<Vec<usize>> {
+    check_dims(matrix_t, criteria_type, tolerance)?;
+    let candidates: Vec<usize> = (0..matrix_t.dim().1).collect();
+    let front = non_dominated(matrix_t, criteria_type, tolerance, &candidates, options);
+
+    Ok(candidates
+        .into_iter()
+        .zip(front.iter())
+        .filter_map(|(i, keep)| keep.then_some(i))
+        .collect())
+}
+
+/// Returns the dominance layer of each alternative, layer 0 being the Pareto front.
+/// Alternatives left in a dominance cycle share the last layer.
+pub fn dominance_layers(
+    matrix_t: ArrayView2<Fl>,
+    criteria_type: ArrayView1<Fl>,
+    tolerance: Option<ArrayView1<Fl>>,
+    options: &ComputeOptions,
+) -> Result<Array1<usize>> {
+    check_dims(matrix_t, criteria_type, tolerance)?;
+    let mut layers = Array1::<usize>::zeros(matrix_t.dim().1);
+    let mut remaining: Vec<usize> = (0..matrix_t.dim().1).collect();
+    let mut layer = 0;
+
+    while !remaining.is_empty() {
+        let front = non_dominated(matrix_t, criteria_type, tolerance, &remaining, options);
+        if !front.iter().any(|keep| *keep) {
+            remaining.iter().for_each(|&i| layers[i] = layer);
+            break;
+        }
+
+        let mut next = vec![];
+        for (&i, keep) in remaining.iter().zip(front.iter()) {
+            if *keep {
+                layers[i] = layer;
+            } else {
+                next.push(i);
+            }
+        }
+        remaining = next;
+        layer += 1;
+    }
+    Ok(layers)
+}
+
+impl Prom {
+    fn tolerance(&self, use_q: bool) -> Option<ArrayView1<'_, Fl>> {
+        use_q.then(|| self.criteria.q.view())
+    }
+
+    /// Returns the Pareto-optimal alternatives, using `q` as tolerances with `use_q`.
+    pub fn pareto_front(&self, use_q: bool) -> Result<Vec<usize>> {
+        pareto_front(
+            self.matrix_t.view(),
+            self.criteria.criteria_type.view(),
+            self.tolerance(use_q),
+            &self.options,
+        )
+    }
+
+    /// Returns the dominance layer of each alternative, see [`dominance_layers`].
+    pub fn dominance_layers(&self, use_q: bool) -> Result<Array1<usize>> {
+        dominance_layers(
+            self.matrix_t.view(),
+            self.criteria.criteria_type.view(),
+            self.tolerance(use_q),
+            &self.options,
+        )
+    }
+
+    /// Returns a new analysis on a subset of the alternatives. Unlabeled alternatives
+    /// are labeled with their index in this analysis.
+    pub fn select_alternatives(&self, indices: &[usize]) -> Result<Prom> {
+        let n = self.matrix_t.dim().1;
+        if let Some(i) = indices.iter().find(|&&i| i >= n) {
+            return Err(MCDMRSError::Error(format!(
+                "Alternative index {} is out of bounds for {} alternatives",
+                i, n
+            ))
+            .into());
+        }
+
+        let labels = Array1::from_iter(indices.iter().map(|&i| self.label(i)));
+        let mut p = Prom::new(
+            self.matrix_t.select(Axis(1), indices),
+            self.criteria.clone(),
+        )?
+        .with_labels(labels)?;
+        p.missing = self.missing;
+        p.options = self.options.clone();
+        Ok(p)
+    }
+
+    /// Returns a new analysis on the Pareto-optimal alternatives only.
+    pub fn non_dominated(&self, use_q: bool) -> Result<Prom> {
+        self.select_alternatives(&self.pareto_front(use_q)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::utils::test_prom;
+    use super::*;
+    use ndarray::{array, Array2};
+
+    fn get_prom() -> Prom {
+        // cost (minimize) and quality (maximize)
+        let mut p = test_prom(
+            array![
+                [1., 2., 3., 2.5, 1.05, 4., Fl::NAN],
+                [1., 3., 4., 2., 0.9, 3., 0.]
+            ],
+            array![-1., 1.],
+        );
+        p.criteria.q = array![0.1, 0.2];
+        p
+    }
+
+    #[test]
+    fn test_dominates() {
+        let p = get_prom();
+        let (m, t) = (p.matrix_t.view(), p.criteria.criteria_type.view());
+        assert!(dominates(m, t, None, 1, 3));
+        assert!(!dominates(m, t, None, 3, 1));
+        assert!(!dominates(m, t, None, 0, 1));
+        assert!(!dominates(m, t, None, 1, 1));
+        assert!(!dominates(m, t, None, 0, 6));
+        assert!(!dominates(m, t, None, 6, 0));
+        // 4 is slightly worse on both criteria, but within the tolerances of 0
+        assert!(dominates(m, t, None, 0, 4));
+        assert!(!dominates(m, t, Some(p.criteria.q.view()), 0, 4));
+    }
+
+    #[test]
+    fn test_pareto_front() -> Result<()> {
+        let p = get_prom();
+        assert_eq!(vec![0, 1, 2, 6], p.pareto_front(false)?);
+        assert_eq!(vec![0, 1, 2, 4, 6], p.pareto_front(true)?);
+        assert_eq!(array![0, 0, 0, 1, 1, 1, 0], p.dominance_layers(false)?);
+
+        let q = array![0.1];
+        assert!(pareto_front(
+            p.matrix_t.view(),
+            p.criteria.criteria_type.view(),
+            Some(q.view()),
+            &ComputeOptions::Global
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pareto_front_brute_force() -> Result<()> {
+        use rand::Rng;
+        let mut rng = rand::thread_rng();
+        let m = Array2::from_shape_fn((3, 300), |_| rng.gen_range(0..20) as Fl);
+        let t = array![1., -1., 1.];
+
+        let expected: Vec<usize> = (0..300)
+            .filter(|&i| !(0..300).any(|j| dominates(m.view(), t.view(), None, j, i)))
+            .collect();
+        assert_eq!(
+            expected,
+            pareto_front(m.view(), t.view(), None, &ComputeOptions::Serial)?
+        );
+
+        let layers = dominance_layers(m.view(), t.view(), None, &ComputeOptions::Global)?;
+        for i in 0..300 {
+            for j in 0..300 {
+                if dominates(m.view(), t.view(), None, i, j) {
+                    assert!(layers[i] < layers[j]);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_dominated() -> Result<()> {
+        let p = get_prom().with_options(ComputeOptions::Serial);
+        let mut filtered = p.non_dominated(false)?;
+        assert_eq!(array!["0", "1", "2", "6"], filtered.alternative_labels());
+        assert!(matches!(filtered.options, ComputeOptions::Serial));
+        filtered.compute_prom_ii()?;
+        assert_eq!(4, filtered.prom_ii.unwrap().score.len());
+        assert!(p.select_alternatives(&[7]).is_err());
+        Ok(())
+    }
+}
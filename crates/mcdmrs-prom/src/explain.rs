@@ -0,0 +1,254 @@
+use super::math::normalize_vec;
+use super::pairwise::preference_degrees;
+use super::promethee::{Prom, PromResultII};
+use super::types::{Fl, MCDMRSError, Result};
+use ndarray::Array1;
+
+/// The share of an alternative's net flow contributed by one criterion.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Contribution {
+    pub criterion: usize,
+    pub name: String,
+    /// The weighted net flow of the alternative on this criterion.
+    pub value: Fl,
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Explanation {
+    pub alternative: usize,
+    pub label: String,
+    pub score: Fl,
+    pub rank: usize,
+    /// The largest positive contributions, largest first.
+    pub positive: Vec<Contribution>,
+    /// The largest negative contributions, most negative first.
+    pub negative: Vec<Contribution>,
+}
+
+/// How one criterion separates a pair of alternatives.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PairContribution {
+    pub criterion: usize,
+    pub name: String,
+    /// The normalized criterion weight.
+    pub weight: Fl,
+    /// The preference degree `P_j(a, b)`.
+    pub a_over_b: Fl,
+    /// The preference degree `P_j(b, a)`.
+    pub b_over_a: Fl,
+    /// `weight * (a_over_b - b_over_a)`, positive when the criterion favors `a`.
+    pub value: Fl,
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PairExplanation {
+    pub a: String,
+    pub b: String,
+    /// The aggregated preference index `pi(a, b)`.
+    pub pi_ab: Fl,
+    /// The aggregated preference index `pi(b, a)`.
+    pub pi_ba: Fl,
+    /// Every criterion, from the one most in favor of `a` to the one most in favor of `b`.
+    pub criteria: Vec<PairContribution>,
+}
+
+impl Prom {
+    fn explanation(
+        &self,
+        pii: &PromResultII,
+        ranks: &Array1<usize>,
+        i: usize,
+        top: usize,
+    ) -> Explanation {
+        let mut contributions: Vec<Contribution> = pii
+            .weighted_flow
+            .row(i)
+            .iter()
+            .enumerate()
+            .map(|(j, v)| Contribution {
+                criterion: j,
+                name: self.criterion_name(j),
+                value: *v,
+            })
+            .collect();
+        contributions.sort_by(|a, b| b.value.total_cmp(&a.value));
+
+        let positive = contributions
+            .iter()
+            .filter(|c| c.value > 0.0)
+            .take(top)
+            .cloned()
+            .collect();
+        let negative = contributions
+            .iter()
+            .rev()
+            .filter(|c| c.value < 0.0)
+            .take(top)
+            .cloned()
+            .collect();
+
+        Explanation {
+            alternative: i,
+            label: self.label(i),
+            score: pii.score[i],
+            rank: ranks[i],
+            positive,
+            negative,
+        }
+    }
+
+    /// Explains the net flow of alternative `i` with its `top` largest positive and
+    /// negative criterion contributions. The contributions of all criteria sum to the
+    /// net flow.
+    pub fn explain(&self, i: usize, top: usize) -> Result<Explanation> {
+        let pii = self.result_ii()?;
+        if i >= pii.score.len() {
+            return Err(MCDMRSError::Error(format!(
+                "Alternative index {} is out of bounds for {} alternatives",
+                i,
+                pii.score.len()
+            ))
+            .into());
+        }
+        Ok(self.explanation(pii, pii.ranks(), i, top))
+    }
+
+    /// Explains every alternative, see [`Prom::explain`].
+    pub fn explain_all(&self, top: usize) -> Result<Vec<Explanation>> {
+        let pii = self.result_ii()?;
+        let ranks = pii.ranks();
+        Ok((0..pii.score.len())
+            .map(|i| self.explanation(pii, ranks, i, top))
+            .collect())
+    }
+
+    /// Shows which criteria drive alternative `a` over `b`, using the pairwise
+    /// preference degrees. Missing values are imputed under
+    /// [`MissingPolicy::Impute`](crate::missing::MissingPolicy::Impute), and the `Valid`
+    /// and `Exclude` policies are not supported.
+    pub fn explain_pair(&self, a: usize, b: usize) -> Result<PairExplanation> {
+        let matrix_t = self.policy_matrix_t("Pairwise explanations")?;
+        let ab = preference_degrees(matrix_t.view(), &self.criteria, a, b)?;
+        let ba = preference_degrees(matrix_t.view(), &self.criteria, b, a)?;
+        let weight = normalize_vec(self.criteria.weight.view());
+
+        let mut criteria: Vec<PairContribution> = (0..weight.len())
+            .map(|j| PairContribution {
+                criterion: j,
+                name: self.criterion_name(j),
+                weight: weight[j],
+                a_over_b: ab[j],
+                b_over_a: ba[j],
+                value: weight[j] * (ab[j] - ba[j]),
+            })
+            .collect();
+        criteria.sort_by(|x, y| y.value.total_cmp(&x.value));
+
+        Ok(PairExplanation {
+            a: self.label(a),
+            b: self.label(b),
+            pi_ab: weight.dot(&ab),
+            pi_ba: weight.dot(&ba),
+            criteria,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::missing::{Impute, MissingPolicy};
+    use super::super::utils::test_prom;
+    use super::*;
+    use ndarray::array;
+
+    fn get_prom() -> Prom {
+        let mut p = test_prom(
+            array![[0.8, 0.2, 0.05], [0.1, 0.6, 0.4], [3., 1., 2.]],
+            array![-1., 1., 1.],
+        );
+        p.criteria.weight = array![1., 1., 2.];
+        p.criteria.name = Some(array![
+            "cost".to_string(),
+            "area".to_string(),
+            "benefit".to_string()
+        ]);
+        p.compute_prom_ii().unwrap();
+        p
+    }
+
+    #[test]
+    fn test_explain() -> Result<()> {
+        let p = get_prom();
+        let e = p.explain(0, 2)?;
+
+        // the first alternative is the most expensive and smallest but has the most benefit
+        assert_eq!(
+            vec!["benefit"],
+            e.positive.iter().map(|c| &c.name).collect::<Vec<_>>()
+        );
+        assert_eq!(2, e.negative.len());
+        assert!(e.negative[0].value <= e.negative[1].value);
+
+        let all = p.explain(0, 3)?;
+        let total: Fl = all
+            .positive
+            .iter()
+            .chain(&all.negative)
+            .map(|c| c.value)
+            .sum();
+        assert!((total - e.score).abs() < 1e-6);
+
+        assert_eq!(1, p.explain(0, 1)?.negative.len());
+        let explanations = p.explain_all(1)?;
+        assert_eq!(3, explanations.len());
+        for (i, e) in explanations.iter().enumerate() {
+            assert_eq!(p.rank_of(&i.to_string())?, e.rank);
+        }
+        assert!(p.explain(3, 1).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_explain_pair() -> Result<()> {
+        let p = get_prom();
+        let e = p.explain_pair(0, 1)?;
+
+        assert_eq!("benefit", e.criteria[0].name);
+        assert_eq!(0.5, e.criteria[0].value);
+        assert_eq!(0.5, e.pi_ab);
+        assert_eq!(0.5, e.pi_ba);
+        assert_eq!(-0.25, e.criteria[2].value);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_explain_pair_missing() -> Result<()> {
+        let mut p = get_prom().with_missing_policy(MissingPolicy::Impute(Impute::Worst));
+        p.matrix_t[[2, 0]] = Fl::NAN;
+        // the worst benefit is 1, so the first alternative no longer wins on it
+        let e = p.explain_pair(0, 1)?;
+        assert_eq!(
+            0.0,
+            e.criteria
+                .iter()
+                .find(|c| c.name == "benefit")
+                .unwrap()
+                .value
+        );
+
+        for missing in [MissingPolicy::Valid, MissingPolicy::Exclude] {
+            assert!(p
+                .clone()
+                .with_missing_policy(missing)
+                .explain_pair(0, 1)
+                .is_err());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,317 @@
+use super::cmp::{ge, le};
+use super::compute::{zip_for_each, ComputeOptions};
+use super::math::normalize_vec;
+use super::promethee::{Criteria, Prom};
+use super::types::{Fl, MCDMRSError, Result};
+use ndarray::{Array1, ArrayView1, ArrayView2, Axis, Zip};
+
+/// How the reference profiles of [`flow_sort`] define the categories.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ProfileKind {
+    /// `k + 1` profiles bounding `k` categories; profile `h` is the upper bound and
+    /// profile `h + 1` the lower bound of category `h`.
+    #[default]
+    Limiting,
+    /// One typical profile per category.
+    Central,
+}
+
+/// Category assignments, where category 0 is the best.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FlowSortResult {
+    /// The assignment by net flow.
+    pub net: Array1<usize>,
+    /// The assignment by positive flow.
+    pub positive: Array1<usize>,
+    /// The assignment by negative flow. When it differs from `positive`, the
+    /// alternative lies between the two categories.
+    pub negative: Array1<usize>,
+    /// The net flow of each alternative when compared to the profiles alone.
+    pub score: Array1<Fl>,
+}
+
+fn check_profiles(
+    matrix_t: ArrayView2<Fl>,
+    criteria: &Criteria,
+    profiles: ArrayView2<Fl>,
+    kind: ProfileKind,
+) -> Result<()> {
+    let (m, k) = profiles.dim();
+    let min_profiles = match kind {
+        ProfileKind::Limiting => 2,
+        ProfileKind::Central => 1,
+    };
+    if m != matrix_t.dim().0 || m != criteria.weight.len() || k < min_profiles {
+        return Err(MCDMRSError::Error(format!(
+            "Profiles must have one row per criterion and at least {} columns",
+            min_profiles
+        ))
+        .into());
+    }
+    if profiles.iter().any(|v| v.is_nan()) {
+        return Err(MCDMRSError::Error("Profiles must not have missing values".to_string()).into());
+    }
+
+    // each profile must be at least as good as the next on every criterion
+    for (row, t) in profiles
+        .axis_iter(Axis(0))
+        .zip(criteria.criteria_type.iter())
+    {
+        if row.windows(2).into_iter().any(|w| w[0] * t < w[1] * t) {
+            return Err(MCDMRSError::Error(
+                "Profiles must be ordered from the best to the worst category".to_string(),
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Returns the first category whose lower bound `reached` accepts, or the last one.
+fn limiting_category(k: usize, reached: impl Fn(usize) -> bool) -> usize {
+    (0..k).find(|&h| reached(h + 1)).unwrap_or(k - 1)
+}
+
+fn central_category(profile: ArrayView1<Fl>, value: Fl) -> usize {
+    profile
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| (*a - value).abs().total_cmp(&(*b - value).abs()))
+        .map_or(0, |(h, _)| h)
+}
+
+/// Sorts the alternatives into categories with FlowSort.
+///
+/// Each alternative is ranked against the reference profiles alone, so an assignment
+/// costs O(profiles * criteria) and does not depend on the other alternatives. The
+/// profile columns are ordered from the best to the worst category.
+pub fn flow_sort(
+    matrix_t: ArrayView2<Fl>,
+    criteria: &Criteria,
+    profiles: ArrayView2<Fl>,
+    kind: ProfileKind,
+    options: &ComputeOptions,
+) -> Result<FlowSortResult> {
+    check_profiles(matrix_t, criteria, profiles, kind)?;
+
+    let (m, k) = profiles.dim();
+    let n = matrix_t.dim().1;
+    let weight = normalize_vec(criteria.weight.view());
+    let funcs = criteria.preference_functions()?;
+    let pref = |j: usize, a: Fl, b: Fl| -> Fl {
+        let t = criteria.criteria_type[j];
+        let diff = (a - b) * t;
+        if diff.is_nan() {
+            return 0.0;
+        }
+        weight[j] * funcs[j].preference(&diff, &criteria.q[j], &criteria.p[j])
+    };
+
+    // the profile to profile comparisons are shared by every alternative
+    let mut profile_plus = Array1::<Fl>::zeros(k);
+    let mut profile_minus = Array1::<Fl>::zeros(k);
+    for j in 0..m {
+        for (r1, &v1) in profiles.row(j).iter().enumerate() {
+            for &v2 in profiles.row(j).iter() {
+                profile_plus[r1] += pref(j, v1, v2);
+                profile_minus[r1] += pref(j, v2, v1);
+            }
+        }
+    }
+
+    let denom = k as Fl;
+    let mut result = FlowSortResult {
+        net: Array1::zeros(n),
+        positive: Array1::zeros(n),
+        negative: Array1::zeros(n),
+        score: Array1::zeros(n),
+    };
+
+    zip_for_each!(
+        options,
+        Zip::from(matrix_t.axis_iter(Axis(1)))
+            .and(&mut result.net)
+            .and(&mut result.positive)
+            .and(&mut result.negative)
+            .and(&mut result.score),
+        |alt: ArrayView1<Fl>,
+         net: &mut usize,
+         positive: &mut usize,
+         negative: &mut usize,
+         score: &mut Fl| {
+            let mut plus = profile_plus.clone();
+            let mut minus = profile_minus.clone();
+            let (mut alt_plus, mut alt_minus) = (0.0, 0.0);
+
+            for (j, &a) in alt.iter().enumerate() {
+                for (h, &r) in profiles.row(j).iter().enumerate() {
+                    let (a_over_r, r_over_a) = (pref(j, a, r), pref(j, r, a));
+                    alt_plus += a_over_r;
+                    alt_minus += r_over_a;
+                    plus[h] += r_over_a;
+                    minus[h] += a_over_r;
+                }
+            }
+
+            plus /= denom;
+            minus /= denom;
+            let (alt_plus, alt_minus) = (alt_plus / denom, alt_minus / denom);
+            let alt_net = alt_plus - alt_minus;
+            let profile_net = &plus - &minus;
+
+            *score = alt_net;
+            (*net, *positive, *negative) = match kind {
+                // an alternative tied with a profile goes to the upper category
+                ProfileKind::Limiting => (
+                    limiting_category(k - 1, |h| ge(&alt_net, &profile_net[h])),
+                    limiting_category(k - 1, |h| ge(&alt_plus, &plus[h])),
+                    limiting_category(k - 1, |h| le(&alt_minus, &minus[h])),
+                ),
+                ProfileKind::Central => (
+                    central_category(profile_net.view(), alt_net),
+                    central_category(plus.view(), alt_plus),
+                    central_category(minus.view(), alt_minus),
+                ),
+            };
+        }
+    );
+
+    Ok(result)
+}
+
+impl Prom {
+    /// Sorts the alternatives after imputing their missing values under
+    /// [`MissingPolicy::Impute`](crate::missing::MissingPolicy::Impute). The `Valid`
+    /// and `Exclude` policies are not supported.
+    pub fn flow_sort(&self, profiles: ArrayView2<Fl>, kind: ProfileKind) -> Result<FlowSortResult> {
+        flow_sort(
+            self.policy_matrix_t("FlowSort")?.view(),
+            &self.criteria,
+            profiles,
+            kind,
+            &self.options,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::cmp::gt;
+    use super::super::missing::{Impute, MissingPolicy};
+    use super::super::utils::test_prom;
+    use super::*;
+    use ndarray::array;
+
+    fn get_prom() -> Prom {
+        let mut p = test_prom(
+            array![[0.9, 0.5, 0.1, 0.6, Fl::NAN], [90., 40., 5., 20., 80.]],
+            array![1., 1.],
+        );
+        p.criteria.pref_function[1] = "linear".to_string();
+        p.criteria.p[1] = 10.;
+        p
+    }
+
+    #[test]
+    fn test_limiting_profiles() -> Result<()> {
+        let p = get_prom();
+        let profiles = array![[0.7, 0.3, 0.0], [70., 30., 0.]];
+        let r = p.flow_sort(profiles.view(), ProfileKind::Limiting)?;
+
+        assert_eq!(array![0, 0, 1, 0, 0], r.net);
+        assert!(gt(&r.score[0], &r.score[1]));
+        // the fourth alternative is between the first two profiles on one criterion and
+        // between the last two on the other, and its positive flow ties with the second
+        // profile, so it goes to the upper category
+        assert_eq!((0, 0), (r.positive[3], r.negative[3]));
+        assert_eq!((1, 1), (r.positive[2], r.negative[2]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_limiting_profile_ties() -> Result<()> {
+        let p = Prom::new(array![[0.3, 0.7, 0.0], [30., 70., 0.]], get_prom().criteria)?;
+        let profiles = array![[0.7, 0.3, 0.0], [70., 30., 0.]];
+        let r = p.flow_sort(profiles.view(), ProfileKind::Limiting)?;
+
+        // each alternative equals the lower bound of a category on every criterion
+        for (i, expected) in [0, 0, 1].into_iter().enumerate() {
+            assert_eq!(
+                (expected, expected, expected),
+                (r.net[i], r.positive[i], r.negative[i])
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_central_profiles() -> Result<()> {
+        let p = get_prom();
+        let profiles = array![[0.8, 0.5, 0.1], [80., 40., 10.]];
+        let r = p.flow_sort(profiles.view(), ProfileKind::Central)?;
+
+        assert_eq!(0, r.net[0]);
+        assert_eq!(1, r.net[1]);
+        assert_eq!(2, r.net[2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_independent_of_other_alternatives() -> Result<()> {
+        let p = get_prom();
+        let profiles = array![[0.7, 0.3, 0.0], [70., 30., 0.]];
+        let all = p.flow_sort(profiles.view(), ProfileKind::Limiting)?;
+
+        let one = flow_sort(
+            p.matrix_t.select(Axis(1), &[2]).view(),
+            &p.criteria,
+            profiles.view(),
+            ProfileKind::Limiting,
+            &ComputeOptions::Serial,
+        )?;
+        assert_eq!(all.net[2], one.net[0]);
+        assert_eq!(all.score[2], one.score[0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_policy() -> Result<()> {
+        let profiles = array![[0.7, 0.3, 0.0], [70., 30., 0.]];
+        let p = get_prom().with_missing_policy(MissingPolicy::Impute(Impute::Best));
+        let imputed = p.flow_sort(profiles.view(), ProfileKind::Limiting)?;
+
+        let mut filled = get_prom();
+        filled.matrix_t[[0, 4]] = 0.9;
+        let expected = filled.flow_sort(profiles.view(), ProfileKind::Limiting)?;
+        assert_eq!(expected.net, imputed.net);
+        assert_eq!(expected.score, imputed.score);
+
+        for missing in [MissingPolicy::Valid, MissingPolicy::Exclude] {
+            let p = get_prom().with_missing_policy(missing);
+            assert!(p.flow_sort(profiles.view(), ProfileKind::Limiting).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_profile_errors() {
+        let p = get_prom();
+        // not ordered from best to worst
+        let profiles = array![[0.3, 0.7], [30., 70.]];
+        assert!(p.flow_sort(profiles.view(), ProfileKind::Limiting).is_err());
+        // a single limiting profile does not define a category
+        let profiles = array![[0.3], [30.]];
+        assert!(p.flow_sort(profiles.view(), ProfileKind::Limiting).is_err());
+        assert!(p.flow_sort(profiles.view(), ProfileKind::Central).is_ok());
+        // wrong number of criteria
+        let profiles = array![[0.3, 0.1]];
+        assert!(p.flow_sort(profiles.view(), ProfileKind::Central).is_err());
+    }
+}
@@ -0,0 +1,323 @@
+use super::compute::ComputeOptions;
+use super::math::{normalize_vec, rank};
+use super::missing::{complete_alternatives, multicriterion_flow_with_policy, MissingPolicy};
+use super::promethee::{prom_i, prom_ii, Criteria};
+use super::types::{Fl, MCDMRSError, Result};
+use ndarray::{s, Array1, Array2, ArrayView1, ArrayView2, Axis};
+use std::collections::hash_map::{Entry, HashMap};
+use std::sync::Arc;
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GroupResult {
+    /// The importance weighted net flow of each alternative.
+    pub score: Array1<Fl>,
+    /// The Promethee II net flows of each decision-maker (decision-makers x alternatives).
+    pub member_score: Array2<Fl>,
+    /// The 1-based rank of each alternative for each decision-maker.
+    pub member_rank: Array2<usize>,
+    /// The importance weighted variance of the member net flows of each alternative.
+    pub flow_variance: Array1<Fl>,
+    /// The difference between the worst and best member rank of each alternative.
+    pub rank_spread: Array1<usize>,
+    /// The number of unicriterion flows computed. Criteria that share a preference
+    /// function, direction and thresholds across decision-makers are computed once.
+    pub unicriterion_flows: usize,
+}
+
+/// A group decision (Promethee GDSS) where each decision-maker scores the same
+/// alternatives with their own criteria profile.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Group {
+    pub matrix_t: Array2<Fl>,
+    pub members: Vec<Criteria>,
+    pub importance: Array1<Fl>,
+    pub result: Option<GroupResult>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub missing: MissingPolicy,
+    /// Where the parallel kernels run.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub options: ComputeOptions,
+}
+
+/// Computes the group net flows, handling missing values as set by `missing`, with
+/// the kernels running as set by `options`.
+pub fn group_prom_ii(
+    matrix_t: ArrayView2<Fl>,
+    members: &[Criteria],
+    importance: ArrayView1<Fl>,
+    missing: MissingPolicy,
+    options: &ComputeOptions,
+) -> Result<GroupResult> {
+    let (m, n) = matrix_t.dim();
+    let k = members.len();
+
+    // the flows are computed one criterion at a time, so blank out the incomplete
+    // alternatives on every criterion for each of them to be left out
+    let mut matrix_t = matrix_t.to_owned();
+    if missing == MissingPolicy::Exclude {
+        let complete = complete_alternatives(matrix_t.view());
+        for (mut col, _) in matrix_t
+            .columns_mut()
+            .into_iter()
+            .zip(complete)
+            .filter(|(_, c)| !c)
+        {
+            col.fill(Fl::NAN);
+        }
+    }
+
+    let mut cache = HashMap::new();
+    let mut member_score = Array2::<Fl>::zeros((k, n));
+
+    for (c, mut row) in members.iter().zip(member_score.axis_iter_mut(Axis(0))) {
+        let mut plus = Array2::<Fl>::zeros((m, n));
+        let mut minus = Array2::<Fl>::zeros((m, n));
+
+        for j in 0..m {
+            // custom functions with the same name may differ between members
+            let custom = c
+                .functions
+                .get(&c.pref_function[j])
+                .map(|f| Arc::as_ptr(f) as *const () as usize);
+            let key = (
+                j,
+                c.pref_function[j].as_str(),
+                custom,
+                c.criteria_type[j].to_bits(),
+                c.q[j].to_bits(),
+                c.p[j].to_bits(),
+            );
+            let mc = match cache.entry(key) {
+                Entry::Occupied(e) => e.into_mut(),
+                Entry::Vacant(e) => {
+                    let col = &matrix_t.slice(s![j..j + 1, ..]) * c.criteria_type[j];
+                    e.insert(multicriterion_flow_with_policy(
+                        col.view(),
+                        c.pref_function.slice(s![j..j + 1]),
+                        c.q.slice(s![j..j + 1]),
+                        c.p.slice(s![j..j + 1]),
+                        &c.functions,
+                        missing,
+                        options,
+                    )?)
+                }
+            };
+            plus.row_mut(j).assign(&mc.pref_matrix_plus_t.row(0));
+            minus.row_mut(j).assign(&mc.pref_matrix_minus_t.row(0));
+        }
+
+        let pi = prom_i(
+            plus.view(),
+            minus.view(),
+            normalize_vec(c.weight.view()).view(),
+        )?;
+        row.assign(&prom_ii(&pi)?.score);
+    }
+
+    let importance = normalize_vec(importance);
+    let score = member_score.t().dot(&importance);
+    let deviation = &member_score - &score;
+    let flow_variance = (&deviation * &deviation).t().dot(&importance);
+
+    let mut member_rank = Array2::<usize>::zeros((k, n));
+    for (score, mut ranks) in member_score.rows().into_iter().zip(member_rank.rows_mut()) {
+        ranks.assign(&rank(score));
+    }
+    let rank_spread = member_rank.map_axis(Axis(0), |r| {
+        r.iter().max().unwrap_or(&0) - r.iter().min().unwrap_or(&0)
+    });
+
+    Ok(GroupResult {
+        score,
+        member_score,
+        member_rank,
+        flow_variance,
+        rank_spread,
+        unicriterion_flows: cache.len(),
+    })
+}
+
+impl Group {
+    pub fn new(
+        matrix_t: Array2<Fl>,
+        members: Vec<Criteria>,
+        importance: Array1<Fl>,
+    ) -> Result<Group> {
+        let m = matrix_t.dim().0;
+        let is_valid = !members.is_empty()
+            && members.len() == importance.len()
+            && importance.iter().all(|w| *w >= 0.0)
+            && importance.sum() > 0.0
+            && members.iter().all(|c| {
+                m == c.weight.len()
+                    && m == c.criteria_type.len()
+                    && m == c.pref_function.len()
+                    && m == c.q.len()
+                    && m == c.p.len()
+            });
+
+        if is_valid {
+            Ok(Group {
+                matrix_t,
+                members,
+                importance,
+                result: None,
+                missing: MissingPolicy::default(),
+                options: ComputeOptions::default(),
+            })
+        } else {
+            Err(MCDMRSError::Error(
+                "Each member must have one criterion per row of `matrix_t` and a non-negative \
+                 importance"
+                    .to_string(),
+            )
+            .into())
+        }
+    }
+
+    /// Sets the missing value policy, discarding any previously computed result.
+    pub fn with_missing_policy(mut self, missing: MissingPolicy) -> Group {
+        self.missing = missing;
+        self.result = None;
+        self
+    }
+
+    /// Sets where the parallel kernels run, see [`ComputeOptions`].
+    pub fn with_options(mut self, options: ComputeOptions) -> Group {
+        self.options = options;
+        self
+    }
+
+    pub fn compute(&mut self) -> Result<()> {
+        self.result = Some(group_prom_ii(
+            self.matrix_t.view(),
+            &self.members,
+            self.importance.view(),
+            self.missing,
+            &self.options,
+        )?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::promethee::Prom;
+    use super::*;
+    use is_close::all_close;
+    use ndarray::array;
+
+    fn criteria(weight: Array1<Fl>, q: Fl) -> Criteria {
+        Criteria::new(
+            weight,
+            array![-1., 1.],
+            array!["usual".to_string(), "vshape".to_string()],
+            array![0., q],
+            array![0., 0.5],
+        )
+        .unwrap()
+    }
+
+    fn matrix() -> Array2<Fl> {
+        array![[0.8, 0.2, 0.05, 0.3], [0.1, 0.6, 0.4, 0.2]]
+    }
+
+    #[test]
+    fn test_group_matches_members() -> Result<()> {
+        let members = vec![
+            criteria(array![1., 1.], 0.),
+            criteria(array![3., 1.], 0.),
+            criteria(array![1., 2.], 0.1),
+        ];
+        let mut g = Group::new(matrix(), members.clone(), array![2., 1., 1.])?;
+        g.compute()?;
+        let r = g.result.unwrap();
+
+        for (c, score) in members.into_iter().zip(r.member_score.rows()) {
+            let mut p = Prom::new(matrix(), c)?;
+            p.compute_prom_ii()?;
+            assert!(all_close!(
+                p.prom_ii.unwrap().score.to_vec(),
+                score.to_vec(),
+                abs_tol = 1e-6
+            ));
+        }
+
+        let expected = (&r.member_score.row(0) * 0.5)
+            + (&r.member_score.row(1) * 0.25)
+            + (&r.member_score.row(2) * 0.25);
+        assert!(all_close!(
+            expected.to_vec(),
+            r.score.to_vec(),
+            abs_tol = 1e-6
+        ));
+
+        // the first criterion is shared by all members, the second has two variants
+        assert_eq!(3, r.unicriterion_flows);
+        assert!(r.flow_variance.iter().all(|v| *v >= 0.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_agreement() -> Result<()> {
+        let members = vec![criteria(array![1., 1.], 0.); 3];
+        let mut g =
+            Group::new(matrix(), members, array![1., 1., 1.])?.with_options(ComputeOptions::Serial);
+        g.compute()?;
+        let r = g.result.unwrap();
+
+        assert_eq!(2, r.unicriterion_flows);
+        assert_eq!(array![0, 0, 0, 0], r.rank_spread);
+        assert!(all_close!(
+            vec![0.; 4],
+            r.flow_variance.to_vec(),
+            abs_tol = 1e-9
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_missing() -> Result<()> {
+        let mut matrix = matrix();
+        matrix[[1, 2]] = Fl::NAN;
+        let members = vec![criteria(array![1., 1.], 0.), criteria(array![3., 1.], 0.1)];
+
+        for missing in [
+            MissingPolicy::Valid,
+            MissingPolicy::Impute(super::super::missing::Impute::Worst),
+            MissingPolicy::Exclude,
+        ] {
+            let mut g = Group::new(matrix.clone(), members.clone(), array![1., 1.])?
+                .with_missing_policy(missing);
+            g.compute()?;
+            let r = g.result.unwrap();
+
+            for (c, score) in members.iter().zip(r.member_score.rows()) {
+                let mut p = Prom::new(matrix.clone(), c.clone())?.with_missing_policy(missing);
+                p.compute_prom_ii()?;
+                let expected = p.prom_ii.unwrap().score;
+                for (a, b) in expected.iter().zip(score) {
+                    assert!(
+                        a.is_nan() && b.is_nan() || (a - b).abs() < 1e-6,
+                        "{}",
+                        missing
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_errors() {
+        let c = criteria(array![1., 1.], 0.);
+        assert!(Group::new(matrix(), vec![c.clone()], array![1., 1.]).is_err());
+        assert!(Group::new(matrix(), vec![c.clone()], array![-1.]).is_err());
+        assert!(Group::new(matrix(), vec![], array![]).is_err());
+        assert!(Group::new(array![[0.8, 0.2]], vec![c], array![1.]).is_err());
+    }
+}
@@ -0,0 +1,547 @@
+use super::promethee::{re_weight, Criteria, Prom};
+use super::types::{Fl, MCDMRSError, Result};
+use ndarray::{Array1, Array2, ArrayView2, Axis};
+
+/// The index of the root node of every [`Hierarchy`].
+pub const ROOT: usize = 0;
+
+/// A cluster or criterion of a [`Hierarchy`].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Node {
+    pub name: String,
+    pub parent: Option<usize>,
+    /// The weight relative to the siblings of the node.
+    pub weight: Fl,
+    /// The criterion index of a leaf, `None` for clusters.
+    pub criterion: Option<usize>,
+}
+
+/// A criteria hierarchy (value tree) with local weights on every node.
+///
+/// The global weight of a criterion is the product of the local weights along its
+/// path from the root, each normalized among its siblings.
+///
+/// # Examples
+///
+/// ```
+/// use mcdmrs_prom::hierarchy::{Hierarchy, ROOT};
+/// let mut h = Hierarchy::new();
+/// let env = h.add_cluster(ROOT, "environmental", 1.).unwrap();
+/// let eco = h.add_cluster(ROOT, "economic", 3.).unwrap();
+/// h.add_criterion(env, "emissions", 0, 1.).unwrap();
+/// h.add_criterion(env, "water", 1, 1.).unwrap();
+/// h.add_criterion(eco, "cost", 2, 1.).unwrap();
+/// assert_eq!(vec![0.125, 0.125, 0.75], h.leaf_weights(3).unwrap().to_vec());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "Nodes"))]
+pub struct Hierarchy {
+    pub nodes: Vec<Node>,
+}
+
+/// The serialized form of a [`Hierarchy`], validated by [`Hierarchy::from_nodes`].
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct Nodes {
+    nodes: Vec<Node>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<Nodes> for Hierarchy {
+    type Error = super::types::AnyError;
+
+    fn try_from(nodes: Nodes) -> Result<Hierarchy> {
+        Hierarchy::from_nodes(nodes.nodes)
+    }
+}
+
+/// The partial net flows of every cluster of a [`Hierarchy`].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClusterScores {
+    pub names: Vec<String>,
+    /// The weighted net flow of each alternative summed over the criteria of each
+    /// cluster (alternatives x clusters).
+    pub score: Array2<Fl>,
+}
+
+impl Default for Hierarchy {
+    fn default() -> Self {
+        Hierarchy::new()
+    }
+}
+
+impl Hierarchy {
+    pub fn new() -> Hierarchy {
+        Hierarchy {
+            nodes: vec![Node {
+                name: "root".to_string(),
+                parent: None,
+                weight: 1.0,
+                criterion: None,
+            }],
+        }
+    }
+
+    /// Returns the hierarchy of `nodes` after checking that the first node is the root
+    /// and that every other node comes after its parent, a cluster.
+    pub fn from_nodes(nodes: Vec<Node>) -> Result<Hierarchy> {
+        let hierarchy = Hierarchy { nodes };
+        hierarchy.validate()?;
+        Ok(hierarchy)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.nodes.first().is_none_or(|n| n.parent.is_some()) {
+            return Err(MCDMRSError::Error(
+                "The first node of a hierarchy must be the root".to_string(),
+            )
+            .into());
+        }
+        let mut criteria = std::collections::HashSet::new();
+        for (i, n) in self.nodes.iter().enumerate().skip(1) {
+            match n.parent {
+                Some(p) if p < i && self.nodes[p].criterion.is_none() => {}
+                _ => {
+                    return Err(MCDMRSError::Error(format!(
+                        "Node `{}` must come after its parent cluster",
+                        n.name
+                    ))
+                    .into())
+                }
+            }
+            check_weight(&n.name, n.weight)?;
+            if self.nodes[..i].iter().any(|other| other.name == n.name) {
+                return Err(MCDMRSError::Error(format!(
+                    "Node `{}` appears more than once",
+                    n.name
+                ))
+                .into());
+            }
+            if let Some(j) = n.criterion.filter(|&j| !criteria.insert(j)) {
+                return Err(
+                    MCDMRSError::Error(format!("Criterion {} appears more than once", j)).into(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn add(
+        &mut self,
+        parent: usize,
+        name: &str,
+        weight: Fl,
+        criterion: Option<usize>,
+    ) -> Result<usize> {
+        match self.nodes.get(parent) {
+            None => {
+                return Err(MCDMRSError::Error(format!("Unknown parent node {}", parent)).into())
+            }
+            Some(p) if p.criterion.is_some() => {
+                return Err(MCDMRSError::Error(format!(
+                    "Criterion `{}` cannot have children",
+                    p.name
+                ))
+                .into())
+            }
+            _ => {}
+        }
+        check_weight(name, weight)?;
+        if self.node(name).is_some() {
+            return Err(
+                MCDMRSError::Error(format!("Node `{}` appears more than once", name)).into(),
+            );
+        }
+
+        self.nodes.push(Node {
+            name: name.to_string(),
+            parent: Some(parent),
+            weight,
+            criterion,
+        });
+        Ok(self.nodes.len() - 1)
+    }
+
+    /// Adds a cluster under `parent` and returns its index.
+    pub fn add_cluster(&mut self, parent: usize, name: &str, weight: Fl) -> Result<usize> {
+        self.add(parent, name, weight, None)
+    }
+
+    /// Adds criterion `criterion` as a leaf under `parent` and returns its index.
+    pub fn add_criterion(
+        &mut self,
+        parent: usize,
+        name: &str,
+        criterion: usize,
+        weight: Fl,
+    ) -> Result<usize> {
+        if self.nodes.iter().any(|n| n.criterion == Some(criterion)) {
+            return Err(MCDMRSError::Error(format!(
+                "Criterion {} appears more than once",
+                criterion
+            ))
+            .into());
+        }
+        self.add(parent, name, weight, Some(criterion))
+    }
+
+    pub fn node(&self, name: &str) -> Option<usize> {
+        self.nodes.iter().position(|n| n.name == name)
+    }
+
+    pub fn children(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        (0..self.nodes.len()).filter(move |&i| self.nodes[i].parent == Some(node))
+    }
+
+    /// Returns the indices of the cluster nodes, excluding the root.
+    pub fn clusters(&self) -> Vec<usize> {
+        (1..self.nodes.len())
+            .filter(|&i| self.nodes[i].criterion.is_none())
+            .collect()
+    }
+
+    /// Returns the criteria under `node`.
+    pub fn leaves(&self, node: usize) -> Vec<usize> {
+        let mut leaves = vec![];
+        let mut stack = vec![node];
+        while let Some(i) = stack.pop() {
+            match self.nodes[i].criterion {
+                Some(j) => leaves.push(j),
+                None => stack.extend(self.children(i)),
+            }
+        }
+        leaves.sort_unstable();
+        leaves
+    }
+
+    pub fn set_weight(&mut self, node: usize, weight: Fl) -> Result<()> {
+        let n = self
+            .nodes
+            .get_mut(node)
+            .ok_or(MCDMRSError::Error(format!("Unknown node {}", node)))?;
+        check_weight(&n.name, weight)?;
+        n.weight = weight;
+        Ok(())
+    }
+
+    /// Returns the global weight of every node.
+    pub fn global_weights(&self) -> Array1<Fl> {
+        let mut sibling_sum = Array1::<Fl>::zeros(self.nodes.len());
+        for n in self.nodes.iter().skip(1) {
+            sibling_sum[n.parent.unwrap_or(ROOT)] += n.weight;
+        }
+
+        // parents are always added before their children
+        let mut global = Array1::<Fl>::zeros(self.nodes.len());
+        global[ROOT] = 1.0;
+        for (i, n) in self.nodes.iter().enumerate().skip(1) {
+            let parent = n.parent.unwrap_or(ROOT);
+            if sibling_sum[parent] > 0.0 {
+                global[i] = global[parent] * n.weight / sibling_sum[parent];
+            }
+        }
+        global
+    }
+
+    /// Returns the global weight of each of the `m` criteria. Every criterion must
+    /// appear exactly once, every cluster must contain at least one criterion and the
+    /// children of a node must not all have zero weights.
+    pub fn leaf_weights(&self, m: usize) -> Result<Array1<Fl>> {
+        self.validate()?;
+        if let Some(c) = self.clusters().iter().find(|&&c| self.leaves(c).is_empty()) {
+            return Err(MCDMRSError::Error(format!(
+                "Cluster `{}` has no criteria",
+                self.nodes[*c].name
+            ))
+            .into());
+        }
+        let parents = (0..self.nodes.len()).filter(|&i| self.nodes[i].criterion.is_none());
+        for i in parents {
+            let mut children = self.children(i).peekable();
+            if children.peek().is_some() && children.all(|c| self.nodes[c].weight == 0.0) {
+                return Err(MCDMRSError::Error(format!(
+                    "The children of `{}` all have zero weights",
+                    self.nodes[i].name
+                ))
+                .into());
+            }
+        }
+
+        let global = self.global_weights();
+        let mut weight = Array1::<Fl>::from_elem(m, Fl::NAN);
+        for (n, g) in self.nodes.iter().zip(global.iter()) {
+            match n.criterion {
+                Some(j) if j < m => weight[j] = *g,
+                Some(j) => {
+                    return Err(MCDMRSError::Error(format!(
+                        "Criterion {} is out of bounds for {} criteria",
+                        j, m
+                    ))
+                    .into())
+                }
+                None => {}
+            }
+        }
+        if let Some(j) = weight.iter().position(|w| w.is_nan()) {
+            return Err(MCDMRSError::Error(format!(
+                "Criterion {} is missing from the hierarchy",
+                j
+            ))
+            .into());
+        }
+        Ok(weight)
+    }
+
+    /// Sums the columns of `weighted_flow` (alternatives x criteria) over the criteria
+    /// of every cluster.
+    pub fn cluster_scores(&self, weighted_flow: ArrayView2<Fl>) -> ClusterScores {
+        let clusters = self.clusters();
+        let mut score = Array2::<Fl>::zeros((weighted_flow.dim().0, clusters.len()));
+        for (&c, mut col) in clusters.iter().zip(score.axis_iter_mut(Axis(1))) {
+            for j in self.leaves(c) {
+                col += &weighted_flow.column(j);
+            }
+        }
+
+        ClusterScores {
+            names: clusters
+                .iter()
+                .map(|&c| self.nodes[c].name.clone())
+                .collect(),
+            score,
+        }
+    }
+}
+
+fn check_weight(name: &str, weight: Fl) -> Result<()> {
+    if weight.is_finite() && weight >= 0.0 {
+        Ok(())
+    } else {
+        Err(MCDMRSError::Error(format!(
+            "The weight of `{}` must be non-negative, got {}",
+            name, weight
+        ))
+        .into())
+    }
+}
+
+impl Criteria {
+    /// Sets the weights from a criteria hierarchy, see [`Hierarchy::leaf_weights`].
+    pub fn with_hierarchy(mut self, hierarchy: Hierarchy) -> Result<Criteria> {
+        self.weight = hierarchy.leaf_weights(self.weight.len())?;
+        self.hierarchy = Some(hierarchy);
+        Ok(self)
+    }
+}
+
+impl Prom {
+    fn hierarchy(&self) -> Result<&Hierarchy> {
+        Ok(self.criteria.hierarchy.as_ref().ok_or(MCDMRSError::Error(
+            "The criteria have no hierarchy".to_string(),
+        ))?)
+    }
+
+    fn hierarchy_node(&self, name: &str) -> Result<usize> {
+        self.hierarchy()?
+            .node(name)
+            .ok_or(MCDMRSError::Error(format!("Unknown hierarchy node `{}`", name)).into())
+    }
+
+    /// Sets the local weight of a hierarchy node and recomputes Promethee II with the
+    /// propagated criteria weights. The flows are reused, and the hierarchy is left
+    /// unchanged when the new weight is invalid.
+    pub fn re_weight_cluster(&mut self, name: &str, weight: Fl) -> Result<()> {
+        let node = self.hierarchy_node(name)?;
+        let mut hierarchy = self.hierarchy()?.clone();
+        hierarchy.set_weight(node, weight)?;
+        let weight = hierarchy.leaf_weights(self.criteria.weight.len())?;
+        let result = re_weight(self, weight.view());
+        self.criteria.hierarchy = Some(hierarchy);
+        result
+    }
+
+    /// Returns the partial net flows of every cluster, see [`Hierarchy::cluster_scores`].
+    pub fn cluster_scores(&self) -> Result<ClusterScores> {
+        let pii = self.prom_ii.as_ref().ok_or(MCDMRSError::Error(
+            "Promethee II has not been computed".to_string(),
+        ))?;
+        Ok(self.hierarchy()?.cluster_scores(pii.weighted_flow.view()))
+    }
+
+    /// Returns the partial net flow of each alternative on one cluster.
+    pub fn cluster_score(&self, name: &str) -> Result<Array1<Fl>> {
+        let node = self.hierarchy_node(name)?;
+        let pii = self.prom_ii.as_ref().ok_or(MCDMRSError::Error(
+            "Promethee II has not been computed".to_string(),
+        ))?;
+        Ok(self
+            .hierarchy()?
+            .leaves(node)
+            .into_iter()
+            .map(|j| pii.weighted_flow.column(j).to_owned())
+            .fold(Array1::zeros(pii.score.len()), |acc, col| acc + col))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::utils::test_prom;
+    use super::*;
+    use is_close::all_close;
+    use ndarray::array;
+
+    fn get_hierarchy() -> Hierarchy {
+        let mut h = Hierarchy::new();
+        let env = h.add_cluster(ROOT, "environmental", 2.).unwrap();
+        let social = h.add_cluster(ROOT, "social", 1.).unwrap();
+        let eco = h.add_cluster(ROOT, "economic", 1.).unwrap();
+        h.add_criterion(env, "emissions", 0, 3.).unwrap();
+        h.add_criterion(env, "water", 3, 1.).unwrap();
+        h.add_criterion(social, "jobs", 1, 1.).unwrap();
+        h.add_criterion(eco, "cost", 2, 1.).unwrap();
+        h
+    }
+
+    fn get_prom() -> Prom {
+        let mut p = test_prom(
+            array![
+                [0.8, 0.2, 0.05, 0.3],
+                [0.1, 0.6, 0.4, 0.2],
+                [3., 1., 2., 4.],
+                [5., 7., 6., 5.]
+            ],
+            array![-1., 1., -1., 1.],
+        );
+        p.criteria = p.criteria.with_hierarchy(get_hierarchy()).unwrap();
+        p
+    }
+
+    #[test]
+    fn test_leaf_weights() {
+        let h = get_hierarchy();
+        assert_eq!(
+            vec![0.375, 0.25, 0.25, 0.125],
+            h.leaf_weights(4).unwrap().to_vec()
+        );
+        assert_eq!(vec![0, 3], h.leaves(h.node("environmental").unwrap()));
+
+        // a criterion is missing, out of bounds or repeated
+        assert!(h.leaf_weights(5).is_err());
+        assert!(h.leaf_weights(3).is_err());
+        let mut h = get_hierarchy();
+        assert!(h.add_criterion(ROOT, "again", 0, 1.).is_err());
+        assert!(h.add_cluster(ROOT, "social", 1.).is_err());
+        assert!(h.add_cluster(1, "negative", -1.).is_err());
+        // criteria are leaves
+        let leaf = h.node("cost").unwrap();
+        assert!(h.add_cluster(leaf, "below", 1.).is_err());
+        // an empty cluster would silently drop weight
+        h.add_cluster(ROOT, "governance", 1.).unwrap();
+        assert!(h.leaf_weights(4).is_err());
+    }
+
+    #[test]
+    fn test_cluster_scores() -> Result<()> {
+        let mut p = get_prom();
+        p.compute_prom_ii()?;
+
+        let clusters = p.cluster_scores()?;
+        assert_eq!(vec!["environmental", "social", "economic"], clusters.names);
+        let total = clusters.score.sum_axis(Axis(1));
+        let score = &p.prom_ii.as_ref().unwrap().score;
+        assert!(all_close!(score.to_vec(), total.to_vec(), abs_tol = 1e-6));
+        assert_eq!(clusters.score.column(1), p.cluster_score("social")?);
+        assert!(p.cluster_score("jobs").is_ok());
+        assert!(p.cluster_score("governance").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_re_weight_cluster() -> Result<()> {
+        let mut p = get_prom();
+        p.compute_prom_ii()?;
+        let before = p.cluster_score("environmental")?;
+
+        p.re_weight_cluster("environmental", 0.)?;
+        assert_eq!(vec![0., 0.5, 0.5, 0.], p.criteria.weight.to_vec());
+        assert!(p.cluster_score("environmental")?.iter().all(|v| *v == 0.0));
+
+        p.re_weight_cluster("environmental", 2.)?;
+        let after = p.cluster_score("environmental")?;
+        assert!(all_close!(before.to_vec(), after.to_vec(), abs_tol = 1e-6));
+        assert!(p.re_weight_cluster("environmental", Fl::NAN).is_err());
+
+        // zero weights on every cluster are rejected without changing the hierarchy
+        p.re_weight_cluster("social", 0.)?;
+        p.re_weight_cluster("economic", 0.)?;
+        let weight = p.criteria.weight.clone();
+        assert!(p.re_weight_cluster("environmental", 0.).is_err());
+        let env = p.hierarchy_node("environmental")?;
+        assert_eq!(2., p.criteria.hierarchy.as_ref().unwrap().nodes[env].weight);
+        assert_eq!(weight, p.criteria.weight);
+        p.re_weight_cluster("social", 1.)?;
+        p.re_weight_cluster("economic", 1.)?;
+
+        // the same as re-weighting the criteria directly
+        p.re_weight_cluster("water", 3.)?;
+        let mut q = get_prom();
+        q.re_weight(array![0.25, 0.25, 0.25, 0.25].view())?;
+        assert!(all_close!(
+            q.prom_ii.as_ref().unwrap().score.to_vec(),
+            p.prom_ii.as_ref().unwrap().score.to_vec(),
+            abs_tol = 1e-6
+        ));
+
+        // weights set directly no longer follow the hierarchy
+        assert!(q.criteria.hierarchy.is_none());
+        assert!(q.re_weight_cluster("water", 1.).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_nodes() {
+        let nodes = get_hierarchy().nodes;
+        assert_eq!(
+            get_hierarchy(),
+            Hierarchy::from_nodes(nodes.clone()).unwrap()
+        );
+
+        // a node before its parent, which also allows cycles
+        let mut cycle = nodes.clone();
+        cycle[1].parent = Some(2);
+        cycle[2].parent = Some(1);
+        assert!(Hierarchy::from_nodes(cycle).is_err());
+        // a missing parent and a criterion as parent
+        let mut bad = nodes.clone();
+        bad[4].parent = Some(10);
+        assert!(Hierarchy::from_nodes(bad).is_err());
+        let mut bad = nodes.clone();
+        bad[5].parent = Some(4);
+        assert!(Hierarchy::from_nodes(bad).is_err());
+        // the root must come first
+        assert!(Hierarchy::from_nodes(nodes[1..].to_vec()).is_err());
+        assert!(Hierarchy::from_nodes(vec![]).is_err());
+
+        // modified nodes are checked before the weights are propagated
+        let mut h = get_hierarchy();
+        h.nodes[1].parent = Some(3);
+        assert!(h.leaf_weights(4).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize() -> Result<()> {
+        let json = serde_json::to_string(&get_hierarchy())?;
+        assert_eq!(get_hierarchy(), serde_json::from_str(&json)?);
+
+        let mut value: serde_json::Value = serde_json::from_str(&json)?;
+        value["nodes"][1]["parent"] = serde_json::json!(2);
+        assert!(serde_json::from_value::<Hierarchy>(value).is_err());
+        Ok(())
+    }
+}
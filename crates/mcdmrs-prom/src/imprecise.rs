@@ -0,0 +1,351 @@
+//! Imprecise weights and the robust relations they admit.
+use super::cmp::REL_TOL;
+use super::compute::{zip_for_each, ComputeOptions};
+use super::lp::{Lp, Outcome, Relation};
+use super::promethee::{Criteria, Prom};
+use super::types::{Fl, MCDMRSError, Result};
+use ndarray::{Array1, Array2, ArrayView2, Axis, Zip};
+
+/// A linear constraint on the weights normalized to sum to 1.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WeightConstraint {
+    /// `min <= w[criterion] <= max`.
+    Range { criterion: usize, min: Fl, max: Fl },
+    /// `w[more] >= w[less] + margin`: criterion `more` is more important than `less`.
+    Order {
+        more: usize,
+        less: usize,
+        margin: Fl,
+    },
+    /// `coefficients . w <= rhs`.
+    Linear { coefficients: Array1<Fl>, rhs: Fl },
+}
+
+impl WeightConstraint {
+    pub(crate) fn check(&self, m: usize) -> Result<()> {
+        let out_of_bounds = |j: usize| -> Result<()> {
+            if j < m {
+                Ok(())
+            } else {
+                Err(MCDMRSError::Error(format!(
+                    "Criterion index {} is out of bounds for {} criteria",
+                    j, m
+                ))
+                .into())
+            }
+        };
+        let finite = match self {
+            WeightConstraint::Range {
+                criterion,
+                min,
+                max,
+            } => {
+                out_of_bounds(*criterion)?;
+                min.is_finite() && max.is_finite()
+            }
+            WeightConstraint::Order { more, less, margin } => {
+                out_of_bounds(*more)?;
+                out_of_bounds(*less)?;
+                margin.is_finite()
+            }
+            WeightConstraint::Linear { coefficients, rhs } => {
+                if coefficients.len() != m {
+                    return Err(MCDMRSError::Error(format!(
+                        "A linear weight constraint needs {} coefficients, got {}",
+                        m,
+                        coefficients.len()
+                    ))
+                    .into());
+                }
+                coefficients.iter().all(|v| v.is_finite()) && rhs.is_finite()
+            }
+        };
+        if finite {
+            Ok(())
+        } else {
+            Err(
+                MCDMRSError::Error(format!("The weight constraint {:?} is not finite", self))
+                    .into(),
+            )
+        }
+    }
+
+    pub(crate) fn add_to(&self, lp: &mut Lp) {
+        let m = lp.n;
+        let unit = |j: usize, v: f64| {
+            let mut row = vec![0.0; m];
+            row[j] = v;
+            row
+        };
+        match self {
+            WeightConstraint::Range {
+                criterion,
+                min,
+                max,
+            } => {
+                let (c, min, max) = (*criterion, *min as f64, *max as f64);
+                lp.constrain(unit(c, 1.0), Relation::Ge, min);
+                lp.constrain(unit(c, 1.0), Relation::Le, max);
+            }
+            WeightConstraint::Order { more, less, margin } => {
+                let mut row = unit(*more, 1.0);
+                row[*less] -= 1.0;
+                lp.constrain(row, Relation::Ge, *margin as f64);
+            }
+            WeightConstraint::Linear { coefficients, rhs } => {
+                let mut row: Vec<f64> = coefficients.iter().map(|v| *v as f64).collect();
+                row.resize(m, 0.0);
+                lp.constrain(row, Relation::Le, *rhs as f64);
+            }
+        }
+    }
+}
+
+/// Returns the non-negative weights summing to 1 that satisfy every constraint.
+pub(crate) fn admissible_weights(m: usize, constraints: &[WeightConstraint]) -> Result<Lp> {
+    let mut lp = Lp::new(m);
+    lp.constrain(vec![1.0; m], Relation::Eq, 1.0);
+    for c in constraints {
+        c.check(m)?;
+        c.add_to(&mut lp);
+    }
+    if !lp.is_feasible() {
+        return Err(
+            MCDMRSError::Error("The weight constraints admit no weights".to_string()).into(),
+        );
+    }
+    Ok(lp)
+}
+
+impl Criteria {
+    /// Sets the weight constraints. `weight` is still used by the plain computations.
+    pub fn with_weight_constraints(
+        mut self,
+        constraints: Vec<WeightConstraint>,
+    ) -> Result<Criteria> {
+        admissible_weights(self.weight.len(), &constraints)?;
+        self.weight_constraints = constraints;
+        Ok(self)
+    }
+}
+
+/// The Promethee II relations over every admissible weight vector.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RobustRelations {
+    /// `a` outranks `b` for every admissible weight vector.
+    pub necessary: Array2<bool>,
+    /// `a` outranks `b` for at least one admissible weight vector.
+    pub possible: Array2<bool>,
+    pub score_min: Array1<Fl>,
+    pub score_max: Array1<Fl>,
+    /// The best possible rank of each alternative.
+    pub best_rank: Array1<usize>,
+    /// The worst possible rank of each alternative.
+    pub worst_rank: Array1<usize>,
+}
+
+impl RobustRelations {
+    /// Returns the necessary relations as `(a, b)` pairs, `a != b`.
+    pub fn necessary_edges(&self) -> Vec<(usize, usize)> {
+        edges(&self.necessary)
+    }
+
+    /// Returns the possible relations as `(a, b)` pairs, `a != b`.
+    pub fn possible_edges(&self) -> Vec<(usize, usize)> {
+        edges(&self.possible)
+    }
+}
+
+fn edges(relation: &Array2<bool>) -> Vec<(usize, usize)> {
+    relation
+        .indexed_iter()
+        .filter_map(|((a, b), r)| (*r && a != b).then_some((a, b)))
+        .collect()
+}
+
+fn optimum(outcome: Outcome) -> Result<f64> {
+    match outcome {
+        Outcome::Optimal { value, .. } => Ok(value),
+        _ => Err(MCDMRSError::Error("The weight constraints admit no weights".to_string()).into()),
+    }
+}
+
+/// Computes the necessary and possible relations from the unweighted unicriterion net
+/// flows (criteria x alternatives), one linear program per pair. Ties outrank both ways.
+pub fn robust_relations(
+    flows: ArrayView2<Fl>,
+    constraints: &[WeightConstraint],
+    options: &ComputeOptions,
+) -> Result<RobustRelations> {
+    let (m, n) = flows.dim();
+    if flows.iter().any(|v| v.is_nan()) {
+        return Err(MCDMRSError::Error(
+            "The flows have missing values, impute them or skip them instead".to_string(),
+        )
+        .into());
+    }
+    let lp = admissible_weights(m, constraints)?;
+    let column = |a: usize| -> Vec<f64> { flows.column(a).iter().map(|v| *v as f64).collect() };
+
+    let mut score_min = Array1::<Fl>::zeros(n);
+    let mut score_max = Array1::<Fl>::zeros(n);
+    for (a, (lo, hi)) in score_min.iter_mut().zip(score_max.iter_mut()).enumerate() {
+        *lo = optimum(lp.minimize(&column(a)))? as Fl;
+        *hi = optimum(lp.maximize(&column(a)))? as Fl;
+    }
+
+    // margin[[a, b]] is the smallest phi(a) - phi(b) over the admissible weights
+    let mut margin = Array2::<f64>::zeros((n, n));
+    zip_for_each!(
+        options,
+        Zip::indexed(&mut margin),
+        |(a, b): (usize, usize), v: &mut f64| {
+            if a != b {
+                let diff: Vec<f64> = flows
+                    .axis_iter(Axis(0))
+                    .map(|row| row[a] as f64 - row[b] as f64)
+                    .collect();
+                // the region is bounded and not empty, so this is always optimal
+                if let Outcome::Optimal { value, .. } = lp.minimize(&diff) {
+                    *v = value;
+                }
+            }
+        }
+    );
+
+    let tol = REL_TOL as f64;
+    let necessary = margin.mapv(|v| v >= -tol);
+    // a possibly outranks b unless b is strictly better for every weight vector
+    let possible = Array2::from_shape_fn((n, n), |(a, b)| margin[[b, a]] <= tol);
+    let best_rank = Array1::from_shape_fn(n, |a| 1 + (0..n).filter(|&b| !possible[[a, b]]).count());
+    let worst_rank =
+        Array1::from_shape_fn(n, |a| n - (0..n).filter(|&b| !possible[[b, a]]).count());
+
+    Ok(RobustRelations {
+        necessary,
+        possible,
+        score_min,
+        score_max,
+        best_rank,
+        worst_rank,
+    })
+}
+
+impl Prom {
+    /// Returns the unweighted unicriterion net flows (criteria x alternatives).
+    pub(crate) fn unicriterion_net_flows(&mut self) -> Result<Array2<Fl>> {
+        if self.mc_flow.is_none() {
+            self.compute_multicriterion_flow()?;
+        }
+        let mc = self.mc_flow.as_ref().unwrap();
+        Ok(&mc.pref_matrix_plus_t - &mc.pref_matrix_minus_t)
+    }
+
+    /// Returns the robust relations under the weight constraints of the criteria.
+    pub fn robust_relations(&mut self) -> Result<RobustRelations> {
+        let flows = self.unicriterion_net_flows()?;
+        robust_relations(
+            flows.view(),
+            &self.criteria.weight_constraints,
+            &self.options,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::utils::test_prom;
+    use super::*;
+    use ndarray::array;
+
+    fn get_prom() -> Prom {
+        // a is cheap, b has the largest area and c is in between
+        test_prom(array![[1., 3., 2.], [1., 3., 2.5]], array![-1., 1.])
+    }
+
+    #[test]
+    fn test_unconstrained() -> Result<()> {
+        let mut p = get_prom();
+        let r = p.robust_relations()?;
+        // every alternative wins for some weights, none for all
+        assert!(r.possible.iter().all(|v| *v));
+        assert_eq!(Vec::<(usize, usize)>::new(), r.necessary_edges());
+        assert_eq!(array![1, 1, 1], r.best_rank);
+        assert_eq!(array![3, 3, 3], r.worst_rank);
+        assert_eq!(array![-1., -1., 0.], r.score_min);
+        assert_eq!(array![1., 1., 0.], r.score_max);
+        Ok(())
+    }
+
+    #[test]
+    fn test_constrained() -> Result<()> {
+        let mut p = get_prom();
+        // cost matters more than area, and a tie is admissible
+        let order = |margin| WeightConstraint::Order {
+            more: 0,
+            less: 1,
+            margin,
+        };
+        p.criteria = p.criteria.with_weight_constraints(vec![order(0.)])?;
+        let r = p.robust_relations()?;
+        assert_eq!(vec![(0, 1), (0, 2), (2, 1)], r.necessary_edges());
+        assert!(r.possible.iter().all(|v| *v));
+
+        // cost matters clearly more than area
+        p.criteria = p.criteria.with_weight_constraints(vec![order(0.2)])?;
+        let r = p.robust_relations()?;
+        assert_eq!(vec![(0, 1), (0, 2), (2, 1)], r.necessary_edges());
+        assert_eq!(r.necessary_edges(), r.possible_edges());
+        assert_eq!(array![1, 3, 2], r.best_rank);
+        assert_eq!(r.best_rank, r.worst_rank);
+
+        // with the area between 60% and 100% the order flips
+        p.criteria = p
+            .criteria
+            .with_weight_constraints(vec![WeightConstraint::Range {
+                criterion: 1,
+                min: 0.6,
+                max: 1.,
+            }])?;
+        let r = p.robust_relations()?;
+        assert_eq!(vec![(1, 0), (1, 2), (2, 0)], r.necessary_edges());
+        assert_eq!(array![3, 1, 2], r.best_rank);
+        assert_eq!(r.best_rank, r.worst_rank);
+
+        // a tie holds both ways
+        let flows = array![[1., 1.], [0., 0.]];
+        let r = robust_relations(flows.view(), &[], &ComputeOptions::Serial)?;
+        assert!(r.necessary.iter().all(|v| *v));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_constraints() {
+        let c = get_prom().criteria;
+        let range = |criterion, min, max| WeightConstraint::Range {
+            criterion,
+            min,
+            max,
+        };
+        assert!(c
+            .clone()
+            .with_weight_constraints(vec![range(2, 0., 1.)])
+            .is_err());
+        assert!(c
+            .clone()
+            .with_weight_constraints(vec![range(0, 0.6, 1.), range(1, 0.6, 1.)])
+            .is_err());
+        assert!(c
+            .clone()
+            .with_weight_constraints(vec![range(0, Fl::NAN, 1.)])
+            .is_err());
+        assert!(c
+            .with_weight_constraints(vec![WeightConstraint::Linear {
+                coefficients: array![1.],
+                rhs: 0.
+            }])
+            .is_err());
+    }
+}
@@ -0,0 +1,494 @@
+//! Ordinal regression: infers the criteria weights, and optionally the thresholds, that
+//! reproduce a decision-maker's pairwise statements under Promethee II.
+use super::cmp::REL_TOL;
+use super::imprecise::WeightConstraint;
+use super::lp::{Lp, Outcome, Relation};
+use super::math::mult_axis_0;
+use super::missing::multicriterion_flow_with_policy;
+use super::promethee::{Criteria, Prom};
+use super::types::{Fl, MCDMRSError, Result};
+use ndarray::{s, Array1, Array2, Axis};
+
+/// The number of threshold candidates tried on each criterion, spread evenly up to the
+/// range of its values.
+const STEPS: usize = 10;
+
+/// The largest net flow difference between two alternatives stated as indifferent, see
+/// [`infer_criteria_with`]. Net flows lie in `[-1, 1]`.
+pub const INDIFFERENCE: Fl = 0.01;
+
+/// A statement of the decision-maker about two alternatives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Statement {
+    /// `better` is strictly preferred to `worse`.
+    Prefer { better: usize, worse: usize },
+    /// `a` and `b` are equally good: their net flows differ by at most the
+    /// indifference tolerance.
+    Indifferent { a: usize, b: usize },
+}
+
+impl Statement {
+    /// Turns a partial reference ranking into statements. `groups` lists the
+    /// alternatives best first, with equally good alternatives in the same group, and
+    /// may leave alternatives out.
+    ///
+    /// Each alternative is stated indifferent to the first of its group and preferred
+    /// to every alternative of the next group, as the indifference tolerance does not
+    /// chain.
+    pub fn from_ranking(groups: &[Vec<usize>]) -> Vec<Statement> {
+        let groups: Vec<&Vec<usize>> = groups.iter().filter(|g| !g.is_empty()).collect();
+        let mut statements = vec![];
+        for (i, group) in groups.iter().enumerate() {
+            for &b in &group[1..] {
+                statements.push(Statement::Indifferent { a: group[0], b });
+            }
+            if let Some(next) = groups.get(i + 1) {
+                for &better in group.iter() {
+                    for &worse in next.iter() {
+                        statements.push(Statement::Prefer { better, worse });
+                    }
+                }
+            }
+        }
+        statements
+    }
+
+    fn alternatives(&self) -> (usize, usize) {
+        match *self {
+            Statement::Prefer { better, worse } => (better, worse),
+            Statement::Indifferent { a, b } => (a, b),
+        }
+    }
+}
+
+/// The result of [`infer_criteria`].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Inference {
+    /// The criteria with the inferred weights (normalized to sum to 1) and thresholds,
+    /// or the original criteria when the statements are inconsistent.
+    pub criteria: Criteria,
+    /// The smallest net flow difference over the strict preferences, 0 when there are
+    /// none.
+    pub margin: Fl,
+    /// A minimal set of statements that no weights reproduce together, empty when the
+    /// statements are consistent.
+    pub inconsistent: Vec<Statement>,
+}
+
+impl Inference {
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistent.is_empty()
+    }
+}
+
+/// Returns the best margin over the weights satisfying `constraints` and every
+/// statement, `None` when they cannot all hold. Variable `m` of the program is the
+/// margin, capped at 1 to keep it bounded. A strict preference must exceed the
+/// `indifference` tolerance, or it could not be told apart from an indifference.
+fn fit(
+    flows: &Array2<f64>,
+    constraints: &[WeightConstraint],
+    statements: &[Statement],
+    indifference: f64,
+) -> Option<(f64, Vec<f64>)> {
+    let m = flows.dim().0;
+    let mut lp = Lp::new(m + 1);
+    let mut row = vec![1.0; m + 1];
+    row[m] = 0.0;
+    lp.constrain(row, Relation::Eq, 1.0);
+    let mut row = vec![0.0; m + 1];
+    row[m] = 1.0;
+    lp.constrain(row, Relation::Le, 1.0);
+    constraints.iter().for_each(|c| c.add_to(&mut lp));
+
+    let mut prefers = false;
+    for s in statements {
+        let (a, b) = s.alternatives();
+        let mut row: Vec<f64> = flows
+            .axis_iter(Axis(0))
+            .map(|flow| flow[a] - flow[b])
+            .collect();
+        match s {
+            Statement::Prefer { .. } => {
+                prefers = true;
+                row.push(-1.0);
+                lp.constrain(row, Relation::Ge, 0.0);
+            }
+            Statement::Indifferent { .. } => {
+                row.push(0.0);
+                lp.constrain(row.clone(), Relation::Le, indifference);
+                lp.constrain(row, Relation::Ge, -indifference);
+            }
+        }
+    }
+
+    let mut objective = vec![0.0; m + 1];
+    objective[m] = 1.0;
+    match lp.maximize(&objective) {
+        Outcome::Optimal { value, mut x }
+            if value > indifference.max(REL_TOL as f64) || !prefers =>
+        {
+            x.truncate(m);
+            Some((if prefers { value } else { 0.0 }, x))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the `(q, p)` candidates for a criterion using `function` whose values span
+/// `range`, or none when the function has no thresholds.
+fn threshold_candidates(function: &str, range: Fl, q: Fl, p: Fl) -> Vec<(Fl, Fl)> {
+    let grid = (1..=STEPS).map(move |k| range * k as Fl / STEPS as Fl);
+    match function {
+        "ushape" => std::iter::once(0.0).chain(grid).map(|t| (t, p)).collect(),
+        "vshape" => grid.map(|t| (q, t)).collect(),
+        "vshape2" | "vshape_2" | "linear" | "level" => {
+            grid.flat_map(|t| [(0.0, t), (0.5 * t, t)]).collect()
+        }
+        _ => vec![],
+    }
+}
+
+/// Searches for criteria weights that reproduce `statements` under Promethee II: the
+/// better alternative of each preference gets the higher net flow and the net flows
+/// of indifferent alternatives differ by at most [`INDIFFERENCE`], see
+/// [`infer_criteria_with`].
+///
+/// The net flows are linear in the weights, so the weights maximizing the smallest
+/// preference margin come from a linear program that also honors the weight
+/// constraints of the criteria. With `thresholds` the `q` and `p` thresholds of the
+/// built-in preference functions that use them are searched too, one criterion at a
+/// time over a grid up to the range of the criterion, keeping the values that widen
+/// the margin.
+///
+/// When no weights reproduce the statements, the result lists a minimal set of
+/// conflicting statements: removing any one of them resolves that conflict.
+pub fn infer_criteria(p: &Prom, statements: &[Statement], thresholds: bool) -> Result<Inference> {
+    infer_criteria_with(p, statements, thresholds, INDIFFERENCE)
+}
+
+/// Like [`infer_criteria`], with the largest net flow difference between indifferent
+/// alternatives set by `indifference`. Every strict preference must then be wider
+/// than `indifference`.
+pub fn infer_criteria_with(
+    p: &Prom,
+    statements: &[Statement],
+    thresholds: bool,
+    indifference: Fl,
+) -> Result<Inference> {
+    if !(0.0..1.0).contains(&indifference) {
+        return Err(MCDMRSError::Error(
+            "The indifference tolerance must be at least 0 and below 1".to_string(),
+        )
+        .into());
+    }
+    let indifference = indifference as f64;
+    let (m, n) = p.matrix_t.dim();
+    if let Some(s) = statements.iter().find(|s| {
+        let (a, b) = s.alternatives();
+        a >= n || b >= n
+    }) {
+        return Err(MCDMRSError::Error(format!(
+            "The statement {:?} is out of bounds for {} alternatives",
+            s, n
+        ))
+        .into());
+    }
+    let constraints = &p.criteria.weight_constraints;
+    constraints.iter().try_for_each(|c| c.check(m))?;
+
+    let mat = mult_axis_0(p.matrix_t.view(), p.criteria.criteria_type.view())?;
+    let (mut q, mut pp) = (p.criteria.q.clone(), p.criteria.p.clone());
+    let flow = |j: usize, q: Fl, pp: Fl| -> Result<Array1<f64>> {
+        let mc = multicriterion_flow_with_policy(
+            mat.slice(s![j..j + 1, ..]),
+            p.criteria.pref_function.slice(s![j..j + 1]),
+            Array1::from_elem(1, q).view(),
+            Array1::from_elem(1, pp).view(),
+            &p.criteria.functions,
+            p.missing,
+            &p.options,
+        )?;
+        let net = &mc.pref_matrix_plus_t.row(0) - &mc.pref_matrix_minus_t.row(0);
+        if net.iter().any(|v| v.is_nan()) {
+            return Err(MCDMRSError::Error(
+                "The flows have missing values, impute them or skip them instead".to_string(),
+            )
+            .into());
+        }
+        Ok(net.mapv(|v| v as f64))
+    };
+    let mut flows = Array2::<f64>::zeros((m, n));
+    for (j, mut row) in flows.axis_iter_mut(Axis(0)).enumerate() {
+        row.assign(&flow(j, q[j], pp[j])?);
+    }
+
+    let score =
+        |flows: &Array2<f64>| fit(flows, constraints, statements, indifference).map(|(v, _)| v);
+    if thresholds {
+        let mut best = score(&flows);
+        for j in 0..m {
+            let values = mat.row(j);
+            let valid = || values.iter().filter(|v| !v.is_nan());
+            let range = valid().fold(Fl::NEG_INFINITY, |a, &b| a.max(b))
+                - valid().fold(Fl::INFINITY, |a, &b| a.min(b));
+            if !range.is_finite() || range <= 0.0 {
+                continue;
+            }
+            let function = p.criteria.pref_function[j].as_str();
+            for (cq, cp) in threshold_candidates(function, range, q[j], pp[j]) {
+                let original = flows.row(j).to_owned();
+                flows.row_mut(j).assign(&flow(j, cq, cp)?);
+                let candidate = score(&flows);
+                if candidate.unwrap_or(-1.0) > best.unwrap_or(-1.0) + REL_TOL as f64 {
+                    (best, q[j], pp[j]) = (candidate, cq, cp);
+                } else {
+                    flows.row_mut(j).assign(&original);
+                }
+            }
+        }
+    }
+
+    match fit(&flows, constraints, statements, indifference) {
+        Some((margin, weight)) => {
+            let mut criteria = p.criteria.clone();
+            criteria.weight = Array1::from_iter(weight.iter().map(|w| *w as Fl));
+            criteria.q = q;
+            criteria.p = pp;
+            // the weights no longer come from the hierarchy
+            criteria.hierarchy = None;
+            Ok(Inference {
+                criteria,
+                margin: margin as Fl,
+                inconsistent: vec![],
+            })
+        }
+        None => {
+            // deletion filter: drop every statement that is not needed for the conflict
+            let mut conflict = statements.to_vec();
+            let mut i = 0;
+            while i < conflict.len() {
+                let mut trial = conflict.clone();
+                trial.remove(i);
+                if fit(&flows, constraints, &trial, indifference).is_none() {
+                    conflict = trial;
+                } else {
+                    i += 1;
+                }
+            }
+            Ok(Inference {
+                criteria: p.criteria.clone(),
+                margin: 0.0,
+                inconsistent: conflict,
+            })
+        }
+    }
+}
+
+impl Prom {
+    /// Infers criteria reproducing `statements`, see [`infer_criteria`].
+    pub fn infer_criteria(&self, statements: &[Statement], thresholds: bool) -> Result<Inference> {
+        infer_criteria(self, statements, thresholds)
+    }
+
+    /// Infers criteria reproducing `statements`, see [`infer_criteria_with`].
+    pub fn infer_criteria_with(
+        &self,
+        statements: &[Statement],
+        thresholds: bool,
+        indifference: Fl,
+    ) -> Result<Inference> {
+        infer_criteria_with(self, statements, thresholds, indifference)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::utils::test_prom;
+    use super::*;
+    use ndarray::array;
+
+    fn get_prom(pref_function: &str) -> Prom {
+        // cost (minimize), area and slope (minimize)
+        let mut p = test_prom(
+            array![
+                [1., 3., 2., 4., 2.5],
+                [1., 3., 2.5, 5., 1.],
+                [3., 1., 2., 2., 4.]
+            ],
+            array![-1., 1., -1.],
+        );
+        p.criteria.pref_function.fill(pref_function.to_string());
+        p
+    }
+
+    #[test]
+    fn test_from_ranking() {
+        let prefer = |better, worse| Statement::Prefer { better, worse };
+        let indifferent = |a, b| Statement::Indifferent { a, b };
+        assert_eq!(
+            vec![
+                prefer(3, 0),
+                prefer(3, 2),
+                prefer(3, 4),
+                indifferent(0, 2),
+                indifferent(0, 4),
+                prefer(0, 1),
+                prefer(2, 1),
+                prefer(4, 1),
+            ],
+            Statement::from_ranking(&[vec![3], vec![0, 2, 4], vec![], vec![1]])
+        );
+    }
+
+    #[test]
+    fn test_from_ranking_groups() -> Result<()> {
+        // 1, 2 and 3 trade one criterion against the other and tie at equal weights
+        let p = test_prom(
+            array![[10., 5., 4., 6., 0.], [10., 5., 6., 4., 0.]],
+            array![1., 1.],
+        );
+        let groups = [vec![0], vec![1, 2, 3], vec![4]];
+        let inferred = p.infer_criteria(&Statement::from_ranking(&groups), false)?;
+        assert!(inferred.is_consistent());
+
+        let mut q = Prom::new(p.matrix_t.clone(), inferred.criteria)?;
+        q.compute_prom_ii()?;
+        let score = &q.prom_ii.as_ref().unwrap().score;
+        for pair in groups.windows(2) {
+            for &better in &pair[0] {
+                for &worse in &pair[1] {
+                    assert!(score[better] > score[worse]);
+                }
+            }
+        }
+        for &i in &groups[1] {
+            assert!((score[i] - score[1]).abs() <= INDIFFERENCE + 1e-5);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_reproduces_ranking() -> Result<()> {
+        // rank the alternatives with hidden weights, then recover weights from the order
+        let mut truth = get_prom("usual");
+        truth.re_weight(array![0.6, 0.3, 0.1].view())?;
+        let score = truth.prom_ii.as_ref().unwrap().score.clone();
+        let mut order: Vec<usize> = (0..5).collect();
+        order.sort_by(|&a, &b| score[b].total_cmp(&score[a]));
+        let groups: Vec<Vec<usize>> = order.iter().map(|&i| vec![i]).collect();
+
+        let p = get_prom("usual");
+        let inferred = p.infer_criteria(&Statement::from_ranking(&groups), false)?;
+        assert!(inferred.is_consistent());
+        assert!(inferred.margin > 0.0);
+        assert!((inferred.criteria.weight.sum() - 1.0).abs() < 1e-5);
+
+        let mut q = Prom::new(p.matrix_t.clone(), inferred.criteria)?;
+        q.compute_prom_ii()?;
+        let score = &q.prom_ii.as_ref().unwrap().score;
+        for pair in order.windows(2) {
+            assert!(score[pair[0]] - score[pair[1]] >= inferred.margin - 1e-5);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_inconsistent() -> Result<()> {
+        let mut p = get_prom("usual");
+        let prefer = |better, worse| Statement::Prefer { better, worse };
+        // 0 > 2 needs the cost and 1 > 0 the area and slope, which already conflict
+        let statements = [prefer(0, 2), prefer(3, 1), prefer(2, 1), prefer(1, 0)];
+        let inferred = p.infer_criteria(&statements, false)?;
+        assert!(!inferred.is_consistent());
+        assert_eq!(vec![prefer(0, 2), prefer(1, 0)], inferred.inconsistent);
+        assert_eq!(p.criteria.weight, inferred.criteria.weight);
+
+        // the weight constraints must hold too
+        let statements = [prefer(3, 2)];
+        assert!(p.infer_criteria(&statements, false)?.is_consistent());
+        p.criteria = p
+            .criteria
+            .with_weight_constraints(vec![WeightConstraint::Range {
+                criterion: 1,
+                min: 0.,
+                max: 0.,
+            }])?;
+        assert!(!p.infer_criteria(&statements, false)?.is_consistent());
+
+        assert!(p.infer_criteria(&[prefer(5, 0)], false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_indifference_tolerance() -> Result<()> {
+        // a single criterion fixes the weights, and 1 and 1.05 are close but not equal
+        let p = Prom::new(
+            array![[1., 1.05, 5., 9.]],
+            Criteria::new(
+                array![1.],
+                array![1.],
+                array!["linear".to_string()],
+                array![0.],
+                array![10.],
+            )?,
+        )?;
+        let statements = Statement::from_ranking(&[vec![3], vec![2], vec![0, 1]]);
+        let inferred = p.infer_criteria(&statements, false)?;
+        assert!(inferred.is_consistent());
+        assert!(inferred.margin > INDIFFERENCE);
+
+        let exact = p.infer_criteria_with(&statements, false, 0.0)?;
+        assert_eq!(
+            vec![Statement::Indifferent { a: 0, b: 1 }],
+            exact.inconsistent
+        );
+
+        // a preference narrower than the tolerance cannot be told from an indifference
+        let statements = [Statement::Prefer {
+            better: 1,
+            worse: 0,
+        }];
+        assert!(p
+            .infer_criteria_with(&statements, false, 0.0)?
+            .is_consistent());
+        assert!(!p.infer_criteria(&statements, false)?.is_consistent());
+        assert!(p.infer_criteria_with(&statements, false, -0.1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_thresholds() -> Result<()> {
+        let p = Prom::new(
+            array![[1., 1.1, 5., 9.]],
+            Criteria::new(
+                array![1.],
+                array![1.],
+                array!["linear".to_string()],
+                array![0.],
+                array![0.],
+            )?,
+        )?;
+        // 1 and 1.1 only tie with an indifference threshold
+        let statements = [
+            Statement::Indifferent { a: 0, b: 1 },
+            Statement::Prefer {
+                better: 2,
+                worse: 0,
+            },
+        ];
+        let inferred = p.infer_criteria(&statements, false)?;
+        assert_eq!(
+            vec![Statement::Indifferent { a: 0, b: 1 }],
+            inferred.inconsistent
+        );
+
+        let inferred = p.infer_criteria(&statements, true)?;
+        assert!(inferred.is_consistent());
+        assert!(inferred.criteria.q[0] >= 0.1);
+        assert!(inferred.criteria.q[0] <= inferred.criteria.p[0]);
+        Ok(())
+    }
+}
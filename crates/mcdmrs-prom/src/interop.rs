@@ -1,66 +1,160 @@
 /// This module make it possible to load your data from a polars dataframe.
 #[cfg(feature = "io")]
 pub mod polars {
-    use super::super::{Criteria, Prom, Result};
-    use ndarray::{Array1, Axis};
+    use super::super::missing::{missing_counts, MissingPolicy};
+    use super::super::types::MCDMRSError;
+    use super::super::validation::validate_criteria;
+    use super::super::{Criteria, Fl, OrdinalScale, Prom, Result};
+    use ndarray::{Array1, Array2, ArrayViewMut1};
     use polars::prelude::{
-        CsvReader, DataFrame, Float32Type, IndexOrder, PolarsResult, SerReader, Series,
+        CsvReader, DataFrame, DataType, NamedFrom, PolarsResult, SerReader, Series,
     };
 
     pub fn df_from_csv(filename: &str) -> PolarsResult<DataFrame> {
         CsvReader::from_path(filename)?.has_header(true).finish()
     }
 
-    fn _pref_func_to_vec_string(ser: &Series) -> Result<Vec<String>> {
-        let new = ser
-            .str()?
+    pub(super) fn _series_to_vec_string(ser: &Series) -> Result<Vec<String>> {
+        ser.str()?
             .into_iter()
-            .map(|s| match s {
-                Some(s) => s.to_string(),
-                None => "usual".to_string(),
+            .enumerate()
+            .map(|(i, s)| {
+                s.map(str::to_string).ok_or(
+                    MCDMRSError::Error(format!(
+                        "missing value in column `{}`, row {}",
+                        ser.name(),
+                        i + 1
+                    ))
+                    .into(),
+                )
             })
-            .collect();
-        Ok(new)
+            .collect()
     }
 
-    fn _series_to_vec_string(ser: &Series) -> Result<Vec<String>> {
-        let new = ser
+    fn _float_column(df: &DataFrame, name: &str) -> Result<Array1<Fl>> {
+        let ser = df.column(name)?.strict_cast(&DataType::Float32)?;
+        Ok(ser
+            .f32()?
+            .into_iter()
+            .map(|v| v.unwrap_or(Fl::NAN))
+            .collect())
+    }
+
+    /// Reads the optional `scale` column, e.g., `low|medium|high`. Empty cells are
+    /// numeric criteria.
+    pub(super) fn _scales(df: &DataFrame) -> Result<Option<Array1<Option<OrdinalScale>>>> {
+        let ser = match df.column("scale") {
+            Ok(ser) => ser.cast(&DataType::String)?,
+            Err(_) => return Ok(None),
+        };
+        let scales = ser
             .str()?
             .into_iter()
-            .map(|s| s.unwrap().to_string())
-            .collect();
-        Ok(new)
+            .map(|s| match s.map(str::trim) {
+                Some(s) if !s.is_empty() => Ok(Some(s.parse::<OrdinalScale>()?)),
+                _ => Ok(None),
+            })
+            .collect::<Result<Array1<Option<OrdinalScale>>>>()?;
+        Ok(Some(scales))
     }
 
+    /// Reads the criteria table, returning every problem reported by
+    /// [`validate_criteria`] as one error.
     pub fn df_to_criteria(df: &DataFrame) -> Result<Criteria> {
-        let float_df = df.select(["weight", "criteria_type", "q", "p"])?;
-        let float_array = float_df.to_ndarray::<Float32Type>(IndexOrder::C)?;
-
+        validate_criteria(df, None).into_result()?;
         Ok(Criteria {
-            weight: float_array
-                .index_axis(Axis(1), float_df.get_column_index("weight").unwrap())
-                .to_owned(),
-            criteria_type: float_array
-                .index_axis(Axis(1), float_df.get_column_index("criteria_type").unwrap())
-                .to_owned(),
-            pref_function: Array1::<String>::from_vec(_pref_func_to_vec_string(
+            weight: _float_column(df, "weight")?,
+            criteria_type: _float_column(df, "criteria_type")?,
+            pref_function: Array1::<String>::from_vec(_series_to_vec_string(
                 df.column("pref_function")?,
             )?),
-            q: float_array
-                .index_axis(Axis(1), float_df.get_column_index("q").unwrap())
-                .to_owned(),
-            p: float_array
-                .index_axis(Axis(1), float_df.get_column_index("p").unwrap())
-                .to_owned(),
+            q: _float_column(df, "q")?,
+            p: _float_column(df, "p")?,
+            name: match df.column("name") {
+                Ok(ser) => Some(Array1::<String>::from_vec(_series_to_vec_string(ser)?)),
+                Err(_) => None,
+            },
+            scale: _scales(df)?,
+            functions: Default::default(),
+            hierarchy: None,
+            weight_constraints: vec![],
         })
     }
 
+    /// Reads the alternative labels from `id_column`, casting the values to strings.
+    pub fn df_to_labels(df: &DataFrame, id_column: &str) -> Result<Array1<String>> {
+        df.column(id_column)?
+            .cast(&DataType::String)?
+            .str()?
+            .into_iter()
+            .enumerate()
+            .map(|(i, s)| match s {
+                Some(s) => Ok(s.to_string()),
+                None => Err(MCDMRSError::Error(format!(
+                    "The id column `{}` has a missing value at row {}",
+                    id_column,
+                    i + 1
+                ))
+                .into()),
+            })
+            .collect()
+    }
+
+    /// Copies the criterion column `ser` into `row`. Labels of ordinal criteria are
+    /// mapped to their scale values and null values become NaN.
+    pub(super) fn fill_row(
+        mut row: ArrayViewMut1<Fl>,
+        name: &str,
+        ser: &Series,
+        scale: Option<&OrdinalScale>,
+    ) -> Result<()> {
+        match scale {
+            Some(scale) => {
+                let labels = ser.cast(&DataType::String)?;
+                row.assign(&scale.map_labels(name, labels.str()?)?);
+            }
+            None => {
+                let values = ser.strict_cast(&DataType::Float32)?;
+                for (v, x) in row.iter_mut().zip(values.f32()?) {
+                    *v = x.unwrap_or(Fl::NAN);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Selects the criteria columns of `data_df` as a (criteria x alternatives) matrix.
+    /// Labels of ordinal criteria are mapped to their scale values and null values
+    /// become NaN.
+    ///
+    /// Each column is copied straight into its row of the matrix, without an
+    /// intermediate (alternatives x criteria) copy.
+    pub fn df_to_matrix_t(data_df: &DataFrame, criteria_df: &DataFrame) -> Result<Array2<Fl>> {
+        let names = _series_to_vec_string(criteria_df.column("name")?)?;
+        let scales = _scales(criteria_df)?;
+
+        let mut matrix_t = Array2::<Fl>::zeros((names.len(), data_df.height()));
+        for (j, (name, row)) in names.iter().zip(matrix_t.rows_mut()).enumerate() {
+            let scale = scales.as_ref().and_then(|s| s[j].as_ref());
+            fill_row(row, name, data_df.column(name)?, scale)?;
+        }
+        Ok(matrix_t)
+    }
+
+    /// Reports the number of missing values in each criteria column of `data_df`.
+    pub fn missing_report(data_df: &DataFrame, criteria_df: &DataFrame) -> Result<DataFrame> {
+        let counts = missing_counts(df_to_matrix_t(data_df, criteria_df)?.view());
+        Ok(DataFrame::new(vec![
+            criteria_df.column("name")?.clone(),
+            Series::new(
+                "missing",
+                counts.iter().map(|&c| c as u32).collect::<Vec<u32>>(),
+            ),
+        ])?)
+    }
+
     pub fn prom_from_polars(data_df: &DataFrame, criteria_df: &DataFrame) -> Result<Prom> {
-        let matrix_t = data_df
-            .select(_series_to_vec_string(criteria_df.column("name")?)?)?
-            .to_ndarray::<Float32Type>(IndexOrder::C)?
-            .t()
-            .to_owned();
+        let matrix_t = df_to_matrix_t(data_df, criteria_df)?;
         let criteria = df_to_criteria(criteria_df)?;
 
         Ok(Prom {
@@ -69,17 +163,57 @@ pub mod polars {
             mc_flow: None,
             prom_i: None,
             prom_ii: None,
+            labels: None,
+            label_index: None,
+            missing: MissingPolicy::default(),
+            pi: None,
+            options: Default::default(),
         })
     }
 
+    /// Builds a [`Prom`] from the columns of `data_df` named by `criteria`.
+    pub fn prom_from_polars_with_criteria(data_df: &DataFrame, criteria: Criteria) -> Result<Prom> {
+        let names = criteria.name.as_ref().ok_or(MCDMRSError::Error(
+            "The criteria must be named to select their columns".to_string(),
+        ))?;
+
+        let mut matrix_t = Array2::<Fl>::zeros((names.len(), data_df.height()));
+        for (j, (name, row)) in names.iter().zip(matrix_t.rows_mut()).enumerate() {
+            let scale = criteria.scale.as_ref().and_then(|s| s[j].as_ref());
+            fill_row(row, name, data_df.column(name)?, scale)?;
+        }
+        Prom::new(matrix_t, criteria)
+    }
+
+    pub fn prom_from_polars_with_labels(
+        data_df: &DataFrame,
+        criteria_df: &DataFrame,
+        id_column: &str,
+    ) -> Result<Prom> {
+        prom_from_polars(data_df, criteria_df)?.with_labels(df_to_labels(data_df, id_column)?)
+    }
+
     pub trait FromPolars {
         fn from_polars(data_df: &DataFrame, criteria_df: &DataFrame) -> Result<Prom>;
+        fn from_polars_with_labels(
+            data_df: &DataFrame,
+            criteria_df: &DataFrame,
+            id_column: &str,
+        ) -> Result<Prom>;
     }
 
     impl FromPolars for Prom {
         fn from_polars(data_df: &DataFrame, criteria_df: &DataFrame) -> Result<Prom> {
             prom_from_polars(data_df, criteria_df)
         }
+
+        fn from_polars_with_labels(
+            data_df: &DataFrame,
+            criteria_df: &DataFrame,
+            id_column: &str,
+        ) -> Result<Prom> {
+            prom_from_polars_with_labels(data_df, criteria_df, id_column)
+        }
     }
 
     #[cfg(test)]
@@ -113,6 +247,115 @@ pub mod polars {
             Ok(())
         }
 
+        #[test]
+        fn test_from_polars_with_labels() -> Result<()> {
+            let criteria_df: DataFrame = df!(
+                "name"=> &["one", "two"],
+                "weight" => &[1., 1.],
+                "criteria_type" => &[-1., 1.],
+                "pref_function" => &["usual", "ushape"],
+                "q" => &[0., 0.],
+                "p" => &[0., 0.],
+            )?;
+
+            let data_df: DataFrame = df!(
+                "id" => &[10, 20, 30],
+                "one"=> &[0.8, 0.2, 0.05],
+                "two" => &[0.1, 0.6, 0.4],
+            )?;
+
+            let p = Prom::from_polars_with_labels(&data_df, &criteria_df, "id")?;
+
+            assert_eq!(
+                Some(Array1::from_vec(vec!["one".to_string(), "two".to_string()])),
+                p.criteria.name
+            );
+            assert_eq!(
+                Some(Array1::from_iter(["10", "20", "30"].map(String::from))),
+                p.labels
+            );
+            assert!(Prom::from_polars_with_labels(&data_df, &criteria_df, "missing").is_err());
+
+            let data_df: DataFrame = df!(
+                "id" => &[Some(10), None, Some(10)],
+                "one"=> &[0.8, 0.2, 0.05],
+                "two" => &[0.1, 0.6, 0.4],
+            )?;
+            let err = Prom::from_polars_with_labels(&data_df, &criteria_df, "id").unwrap_err();
+            assert!(err.to_string().contains("missing value at row 2"));
+            let data_df: DataFrame = df!(
+                "id" => &[10, 20, 10],
+                "one"=> &[0.8, 0.2, 0.05],
+                "two" => &[0.1, 0.6, 0.4],
+            )?;
+            assert!(Prom::from_polars_with_labels(&data_df, &criteria_df, "id").is_err());
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_ordinal_criteria() -> Result<()> {
+            let criteria_df: DataFrame = df!(
+                "name"=> &["cost", "risk", "quality"],
+                "weight" => &[1., 1., 1.],
+                "criteria_type" => &[-1., -1., 1.],
+                "pref_function" => &["usual", "usual", "usual"],
+                "q" => &[0., 0., 0.],
+                "p" => &[0., 0., 0.],
+                "scale" => &[None, Some("low|medium|high"), Some("poor=0|fair=1|good=5")],
+            )?;
+
+            let data_df: DataFrame = df!(
+                "cost"=> &[0.8, 0.2, 0.05],
+                "risk" => &[Some("high"), None, Some("low")],
+                "quality" => &["good", "poor", "fair"],
+            )?;
+
+            let mut p = Prom::from_polars(&data_df, &criteria_df)?;
+            assert_eq!(
+                Some(&"low|medium|high".parse::<OrdinalScale>()?),
+                p.criteria.scale.as_ref().unwrap()[1].as_ref()
+            );
+            assert!(p.criteria.scale.as_ref().unwrap()[0].is_none());
+            assert_eq!(2.0, p.matrix_t[[1, 0]]);
+            assert!(p.matrix_t[[1, 1]].is_nan());
+            assert_eq!(vec![5.0, 0.0, 1.0], p.matrix_t.row(2).to_vec());
+            p.compute_prom_ii()?;
+
+            let data_df: DataFrame = df!(
+                "cost"=> &[0.8, 0.2, 0.05],
+                "risk" => &["high", "extreme", "low"],
+                "quality" => &["good", "poor", "fair"],
+            )?;
+            let err = Prom::from_polars(&data_df, &criteria_df).unwrap_err();
+            assert!(format!("{:#}", err).contains("unknown label `extreme` for criterion `risk`"));
+
+            Ok(())
+        }
+
+        #[test]
+        fn test_missing_report() -> Result<()> {
+            let criteria_df: DataFrame = df!(
+                "name"=> &["one", "two"],
+                "weight" => &[1., 1.],
+                "criteria_type" => &[-1., 1.],
+                "pref_function" => &["usual", "ushape"],
+                "q" => &[0., 0.],
+                "p" => &[0., 0.],
+            )?;
+
+            let data_df: DataFrame = df!(
+                "one"=> &[Some(0.8), None, Some(0.05)],
+                "two" => &[0.1, 0.6, f64::NAN],
+            )?;
+
+            let report = missing_report(&data_df, &criteria_df)?;
+            let missing: Vec<Option<u32>> = report.column("missing")?.u32()?.into_iter().collect();
+            assert_eq!(vec![Some(1), Some(1)], missing);
+
+            Ok(())
+        }
+
         #[test]
         fn test_from_polars_missing_col() -> Result<()> {
             let criteria_df: DataFrame = df!(
@@ -138,5 +381,563 @@ pub mod polars {
 
             Ok(())
         }
+
+        #[test]
+        fn test_from_polars_invalid_criteria() -> Result<()> {
+            let criteria_df: DataFrame = df!(
+                "name"=> &["one", "two"],
+                "weight" => &[Some(1.), None],
+                "criteria_type" => &[-1., 1.],
+                "pref_function" => &[None, Some("ushape")],
+                "q" => &[0., 0.],
+                "p" => &[0., 0.],
+            )?;
+
+            let data_df: DataFrame = df!(
+                "one"=> &[0.8, 0.2, 0.05],
+                "two" => &[0.1, 0.6, 0.4],
+            )?;
+
+            let err = Prom::from_polars(&data_df, &criteria_df)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("row 1, column `pref_function`: missing value"));
+            assert!(err.contains("row 2, column `weight`: missing value"));
+            Ok(())
+        }
+    }
+}
+
+/// This module reads data frames from csv, Arrow IPC, Parquet, newline-delimited JSON
+/// and XLSX files, choosing the reader from the file extension.
+#[cfg(feature = "io")]
+pub mod files {
+    use super::super::types::MCDMRSError;
+    use super::super::Result;
+    #[cfg(any(feature = "ipc", feature = "parquet"))]
+    use super::columnar::ColumnarFormat;
+    use polars::prelude::{
+        CsvReader, DataFrame, DataType, NamedFrom, NullValues, SerReader, Series,
+    };
+    use std::fs::File;
+    use std::path::Path;
+
+    /// Options for reading csv files.
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    pub struct CsvOptions {
+        /// The field delimiter, by default a tab for `.tsv` files and `,` otherwise.
+        pub delimiter: Option<u8>,
+        /// Values read as missing, in addition to empty fields.
+        pub null_values: Vec<String>,
+        /// Whether numbers are written with a decimal comma, e.g., `1,5`. Requires a
+        /// delimiter other than `,`.
+        pub decimal_comma: bool,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum FileFormat {
+        Csv,
+        #[cfg(feature = "ipc")]
+        Ipc,
+        #[cfg(feature = "parquet")]
+        Parquet,
+        #[cfg(feature = "ndjson")]
+        Ndjson,
+        #[cfg(feature = "xlsx")]
+        Xlsx,
+    }
+
+    impl FileFormat {
+        /// Infers the format from the file extension of `path`. Files with a `csv`,
+        /// `txt` or `tsv` extension, or none, are read as csv. Other extensions, and
+        /// known formats whose feature is not enabled, are an error.
+        pub fn from_path(path: &Path) -> Result<FileFormat> {
+            let extension = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or_default()
+                .to_lowercase();
+            let feature = match extension.as_str() {
+                "arrow" | "ipc" | "feather" => "ipc",
+                "parquet" | "pq" => "parquet",
+                "ndjson" | "jsonl" => "ndjson",
+                "xlsx" => "xlsx",
+                "csv" | "txt" | "tsv" | "" => return Ok(FileFormat::Csv),
+                _ => {
+                    return Err(
+                        MCDMRSError::Error(format!("unsupported format `{}`", extension)).into(),
+                    )
+                }
+            };
+            match feature {
+                #[cfg(feature = "ipc")]
+                "ipc" => Ok(FileFormat::Ipc),
+                #[cfg(feature = "parquet")]
+                "parquet" => Ok(FileFormat::Parquet),
+                #[cfg(feature = "ndjson")]
+                "ndjson" => Ok(FileFormat::Ndjson),
+                #[cfg(feature = "xlsx")]
+                "xlsx" => Ok(FileFormat::Xlsx),
+                _ => Err(MCDMRSError::Error(format!(
+                    "reading `.{}` files requires the `{}` feature",
+                    extension, feature
+                ))
+                .into()),
+            }
+        }
+
+        /// Returns the format as a [`ColumnarFormat`], whose criteria columns can be
+        /// read without loading the whole file.
+        #[cfg(any(feature = "ipc", feature = "parquet"))]
+        pub fn columnar(self) -> Option<ColumnarFormat> {
+            match self {
+                #[cfg(feature = "ipc")]
+                FileFormat::Ipc => Some(ColumnarFormat::Ipc),
+                #[cfg(feature = "parquet")]
+                FileFormat::Parquet => Some(ColumnarFormat::Parquet),
+                _ => None,
+            }
+        }
+    }
+
+    pub(super) fn open(path: &Path) -> Result<File> {
+        Ok(File::open(path)
+            .map_err(|e| MCDMRSError::Error(format!("failed to open {}: {}", path.display(), e)))?)
+    }
+
+    /// Reads the file at `path` in the format given by its extension, see
+    /// [`FileFormat::from_path`]. `csv` only applies to csv files.
+    pub fn df_from_file(path: &Path, csv: &CsvOptions) -> Result<DataFrame> {
+        match FileFormat::from_path(path)? {
+            FileFormat::Csv => df_from_csv_with(path, csv),
+            #[cfg(feature = "ipc")]
+            FileFormat::Ipc => Ok(polars::prelude::IpcReader::new(open(path)?).finish()?),
+            #[cfg(feature = "parquet")]
+            FileFormat::Parquet => Ok(polars::prelude::ParquetReader::new(open(path)?).finish()?),
+            #[cfg(feature = "ndjson")]
+            FileFormat::Ndjson => Ok(polars::prelude::JsonReader::new(open(path)?)
+                .with_json_format(polars::prelude::JsonFormat::JsonLines)
+                .finish()?),
+            #[cfg(feature = "xlsx")]
+            FileFormat::Xlsx => df_from_xlsx(path, None),
+        }
+    }
+
+    /// Reads a csv file with a header row.
+    pub fn df_from_csv_with(path: &Path, options: &CsvOptions) -> Result<DataFrame> {
+        let delimiter =
+            options
+                .delimiter
+                .unwrap_or_else(|| match path.extension().and_then(|e| e.to_str()) {
+                    Some(e) if e.eq_ignore_ascii_case("tsv") => b'\t',
+                    _ => b',',
+                });
+        if options.decimal_comma && delimiter == b',' {
+            return Err(MCDMRSError::Error(
+                "a decimal comma requires a delimiter other than `,`".to_string(),
+            )
+            .into());
+        }
+        let null_values = (!options.null_values.is_empty())
+            .then(|| NullValues::AllColumns(options.null_values.clone()));
+
+        let df = CsvReader::new(open(path)?)
+            .has_header(true)
+            .with_separator(delimiter)
+            .with_null_values(null_values)
+            .finish()?;
+
+        if options.decimal_comma {
+            parse_decimal_comma(df)
+        } else {
+            Ok(df)
+        }
+    }
+
+    /// Converts the string columns whose values are all numbers written with a decimal
+    /// comma to floats.
+    fn parse_decimal_comma(mut df: DataFrame) -> Result<DataFrame> {
+        let names: Vec<String> = df
+            .get_columns()
+            .iter()
+            .filter(|s| s.dtype() == &DataType::String)
+            .map(|s| s.name().to_string())
+            .collect();
+
+        for name in names {
+            let values: Option<Vec<Option<f64>>> = df
+                .column(&name)?
+                .str()?
+                .into_iter()
+                .map(|v| match v {
+                    Some(v) => v.trim().replace(',', ".").parse().ok().map(Some),
+                    None => Some(None),
+                })
+                .collect();
+            if let Some(values) = values {
+                df.with_column(Series::new(&name, values))?;
+            }
+        }
+        Ok(df)
+    }
+
+    /// Reads a worksheet of an XLSX workbook with a header row, the first one when
+    /// `sheet` is `None`.
+    ///
+    /// Columns holding only whole numbers become integers, other columns holding only
+    /// numbers become floats and the remaining columns strings. Empty cells are null.
+    #[cfg(feature = "xlsx")]
+    pub fn df_from_xlsx(path: &Path, sheet: Option<&str>) -> Result<DataFrame> {
+        use calamine::{open_workbook, Data, Reader, Xlsx};
+
+        let mut workbook: Xlsx<_> = open_workbook(path)
+            .map_err(|e| MCDMRSError::Error(format!("failed to open {}: {}", path.display(), e)))?;
+        let range = match sheet {
+            Some(name) => workbook.worksheet_range(name)?,
+            None => workbook
+                .worksheet_range_at(0)
+                .ok_or(MCDMRSError::Error(format!(
+                    "{} has no worksheets",
+                    path.display()
+                )))??,
+        };
+
+        let mut rows = range.rows();
+        let header = match rows.next() {
+            Some(header) => header,
+            None => return Ok(DataFrame::default()),
+        };
+        let body: Vec<&[Data]> = rows.collect();
+
+        let mut columns = vec![];
+        for (j, name) in header.iter().enumerate() {
+            let name = name.to_string();
+            let cells = || body.iter().map(move |row| &row[j]);
+            let numbers: Option<Vec<Option<f64>>> = cells()
+                .map(|c| match c {
+                    Data::Int(v) => Some(Some(*v as f64)),
+                    Data::Float(v) => Some(Some(*v)),
+                    Data::Empty => Some(None),
+                    _ => None,
+                })
+                .collect();
+
+            columns.push(match numbers {
+                Some(numbers) if numbers.iter().flatten().all(|v| v.fract() == 0.0) => {
+                    let ints: Vec<Option<i64>> =
+                        numbers.iter().map(|v| v.map(|v| v as i64)).collect();
+                    Series::new(&name, ints)
+                }
+                Some(numbers) => Series::new(&name, numbers),
+                None => {
+                    let strings: Vec<Option<String>> = cells()
+                        .map(|c| match c {
+                            Data::Empty => None,
+                            c => Some(c.to_string()),
+                        })
+                        .collect();
+                    Series::new(&name, strings)
+                }
+            });
+        }
+        Ok(DataFrame::new(columns)?)
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+        use std::path::PathBuf;
+
+        fn temp_file(name: &str, contents: &str) -> Result<PathBuf> {
+            let path = std::env::temp_dir().join(name);
+            std::fs::write(&path, contents)?;
+            Ok(path)
+        }
+
+        #[test]
+        fn test_from_path() -> Result<()> {
+            assert_eq!(FileFormat::Csv, FileFormat::from_path(Path::new("a.csv"))?);
+            assert_eq!(FileFormat::Csv, FileFormat::from_path(Path::new("a.txt"))?);
+            assert_eq!(FileFormat::Csv, FileFormat::from_path(Path::new("a"))?);
+            #[cfg(feature = "parquet")]
+            assert_eq!(
+                FileFormat::Parquet,
+                FileFormat::from_path(Path::new("a.PQ"))?
+            );
+            #[cfg(feature = "ndjson")]
+            assert_eq!(
+                FileFormat::Ndjson,
+                FileFormat::from_path(Path::new("a.jsonl"))?
+            );
+            #[cfg(not(feature = "xlsx"))]
+            assert!(FileFormat::from_path(Path::new("a.xlsx")).is_err());
+            let err = FileFormat::from_path(Path::new("a.gpkg")).unwrap_err();
+            assert_eq!("ERROR: unsupported format `gpkg`", err.to_string());
+            Ok(())
+        }
+
+        #[test]
+        fn test_csv_options() -> Result<()> {
+            let path = temp_file(
+                "mcdmrs_test_options.csv",
+                "id;cost;risk\na;1,5;NA\nb;2;3\nc;-0,25;\n",
+            )?;
+            let options = CsvOptions {
+                delimiter: Some(b';'),
+                null_values: vec!["NA".to_string()],
+                decimal_comma: true,
+            };
+
+            let df = df_from_csv_with(&path, &options)?;
+            let cost: Vec<Option<f64>> = df.column("cost")?.f64()?.into_iter().collect();
+            assert_eq!(vec![Some(1.5), Some(2.0), Some(-0.25)], cost);
+            assert_eq!(2, df.column("risk")?.null_count());
+            assert_eq!(&DataType::String, df.column("id")?.dtype());
+
+            let options = CsvOptions {
+                decimal_comma: true,
+                ..Default::default()
+            };
+            assert!(df_from_csv_with(&path, &options).is_err());
+            Ok(())
+        }
+
+        #[test]
+        fn test_tsv_delimiter() -> Result<()> {
+            let path = temp_file("mcdmrs_test_tabs.tsv", "id\tcost\na\t1.5\nb\t2\n")?;
+            let df = df_from_file(&path, &CsvOptions::default())?;
+            assert_eq!(vec!["id", "cost"], df.get_column_names());
+
+            // an explicit delimiter wins over the extension
+            let options = CsvOptions {
+                delimiter: Some(b','),
+                ..Default::default()
+            };
+            assert_eq!(1, df_from_file(&path, &options)?.width());
+            Ok(())
+        }
+
+        #[cfg(feature = "ndjson")]
+        #[test]
+        fn test_ndjson() -> Result<()> {
+            let path = temp_file(
+                "mcdmrs_test_lines.ndjson",
+                "{\"id\": \"a\", \"cost\": 1.5}\n{\"id\": \"b\", \"cost\": null}\n",
+            )?;
+            let df = df_from_file(&path, &CsvOptions::default())?;
+            assert_eq!((2, 2), df.shape());
+            assert_eq!(1, df.column("cost")?.null_count());
+            Ok(())
+        }
+
+        #[cfg(feature = "xlsx")]
+        #[test]
+        fn test_xlsx() -> Result<()> {
+            let path = std::env::temp_dir().join("mcdmrs_test_sheet.xlsx");
+            let mut workbook = rust_xlsxwriter::Workbook::new();
+            let sheet = workbook.add_worksheet();
+            for (j, name) in ["id", "cost", "risk"].iter().enumerate() {
+                sheet.write_string(0, j as u16, *name)?;
+            }
+            for (i, (id, cost, risk)) in [(10., 1.5, "low"), (20., 2.0, "high")].iter().enumerate()
+            {
+                let row = i as u32 + 1;
+                sheet.write_number(row, 0, *id)?;
+                sheet.write_number(row, 1, *cost)?;
+                sheet.write_string(row, 2, *risk)?;
+            }
+            workbook.save(&path)?;
+
+            let df = df_from_file(&path, &CsvOptions::default())?;
+            assert_eq!(&DataType::Int64, df.column("id")?.dtype());
+            assert_eq!(&DataType::Float64, df.column("cost")?.dtype());
+            assert_eq!(&DataType::String, df.column("risk")?.dtype());
+            assert!(df_from_xlsx(&path, Some("missing")).is_err());
+            Ok(())
+        }
+    }
+}
+
+/// This module reads the alternatives from Arrow IPC and Parquet files, loading only
+/// the criteria columns.
+#[cfg(any(feature = "ipc", feature = "parquet"))]
+pub mod columnar {
+    use super::super::{Fl, Prom, Result};
+    use super::files::{open, FileFormat};
+    use super::polars::{_scales, _series_to_vec_string, df_to_criteria, df_to_labels, fill_row};
+    use ndarray::Array2;
+    use polars::prelude::{DataFrame, SerReader};
+    use std::path::Path;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum ColumnarFormat {
+        /// Arrow IPC (Feather v2). Uncompressed files are memory mapped, so reading a
+        /// column does not copy it.
+        #[cfg(feature = "ipc")]
+        Ipc,
+        /// Parquet. The file is memory mapped and only the column chunks of the
+        /// requested columns are decoded.
+        #[cfg(feature = "parquet")]
+        Parquet,
+    }
+
+    impl ColumnarFormat {
+        /// Infers the format from the file extension of `path`, see
+        /// [`FileFormat::from_path`].
+        pub fn from_path(path: &Path) -> Option<ColumnarFormat> {
+            FileFormat::from_path(path).ok()?.columnar()
+        }
+    }
+
+    /// Reads the `columns` of the file at `path`, and nothing else.
+    pub fn read_columns(
+        path: &Path,
+        format: ColumnarFormat,
+        columns: &[String],
+    ) -> Result<DataFrame> {
+        let file = open(path)?;
+        let columns = Some(columns.to_vec());
+
+        let df = match format {
+            #[cfg(feature = "ipc")]
+            ColumnarFormat::Ipc => polars::prelude::IpcReader::new(file)
+                .memory_mapped(true)
+                .with_columns(columns)
+                .finish()?,
+            #[cfg(feature = "parquet")]
+            ColumnarFormat::Parquet => polars::prelude::ParquetReader::new(file)
+                .with_columns(columns)
+                .finish()?,
+        };
+        Ok(df)
+    }
+
+    /// Returns the column names of the file at `path`, reading only its schema.
+    pub fn column_names(path: &Path, format: ColumnarFormat) -> Result<Vec<String>> {
+        let schema = match format {
+            #[cfg(feature = "ipc")]
+            ColumnarFormat::Ipc => polars::prelude::IpcReader::new(open(path)?).schema()?,
+            #[cfg(feature = "parquet")]
+            ColumnarFormat::Parquet => polars::prelude::ParquetReader::new(open(path)?).schema()?,
+        };
+        Ok(schema.fields.iter().map(|f| f.name.to_string()).collect())
+    }
+
+    /// Reads the criteria columns named in `criteria_df` from the file at `path` as a
+    /// (criteria x alternatives) matrix.
+    ///
+    /// The criteria columns are read in a single pass that skips every other column,
+    /// and each is copied straight into its row of the matrix. The file is memory
+    /// mapped, so the columns of an uncompressed IPC file are not copied before that.
+    pub fn read_matrix_t(
+        path: &Path,
+        format: ColumnarFormat,
+        criteria_df: &DataFrame,
+    ) -> Result<Array2<Fl>> {
+        let names = _series_to_vec_string(criteria_df.column("name")?)?;
+        let scales = _scales(criteria_df)?;
+
+        let df = read_columns(path, format, &names)?;
+        let mut matrix_t = Array2::<Fl>::zeros((names.len(), df.height()));
+        for (j, name) in names.iter().enumerate() {
+            let scale = scales.as_ref().and_then(|s| s[j].as_ref());
+            fill_row(matrix_t.row_mut(j), name, df.column(name)?, scale)?;
+        }
+        Ok(matrix_t)
+    }
+
+    /// Reads the analysis of the alternatives in the file at `path`, labeled with
+    /// `id_column` when given. See [`read_matrix_t`].
+    pub fn prom_from_file(
+        path: &Path,
+        format: ColumnarFormat,
+        criteria_df: &DataFrame,
+        id_column: Option<&str>,
+    ) -> Result<Prom> {
+        let p = Prom::new(
+            read_matrix_t(path, format, criteria_df)?,
+            df_to_criteria(criteria_df)?,
+        )?;
+        match id_column {
+            Some(id) => {
+                let df = read_columns(path, format, &[id.to_string()])?;
+                p.with_labels(df_to_labels(&df, id)?)
+            }
+            None => Ok(p),
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::super::polars::prom_from_polars_with_labels;
+        use super::*;
+        use polars::prelude::*;
+
+        fn frames() -> PolarsResult<(DataFrame, DataFrame)> {
+            let criteria_df = df!(
+                "name"=> &["one", "two", "risk"],
+                "weight" => &[1., 1., 1.],
+                "criteria_type" => &[-1., 1., -1.],
+                "pref_function" => &["usual", "ushape", "usual"],
+                "q" => &[0., 0., 0.],
+                "p" => &[0., 0., 0.],
+                "scale" => &[None, None, Some("low|medium|high")],
+            )?;
+            let data_df = df!(
+                "id" => &["a", "b", "c", "d"],
+                "one"=> &[Some(0.8), Some(0.2), None, Some(0.3)],
+                "skipped" => &[1, 2, 3, 4],
+                "two" => &[1, 6, 4, 2],
+                "risk" => &["high", "low", "medium", "low"],
+            )?;
+            Ok((data_df, criteria_df))
+        }
+
+        fn check(format: ColumnarFormat, extension: &str) -> Result<()> {
+            let (mut data_df, mut criteria_df) = frames()?;
+            let path = std::env::temp_dir().join(format!("mcdmrs_columnar_test.{}", extension));
+            assert_eq!(Some(format), ColumnarFormat::from_path(&path));
+
+            let file = std::fs::File::create(&path)?;
+            match format {
+                #[cfg(feature = "ipc")]
+                ColumnarFormat::Ipc => IpcWriter::new(file).finish(&mut data_df)?,
+                #[cfg(feature = "parquet")]
+                ColumnarFormat::Parquet => {
+                    ParquetWriter::new(file).finish(&mut data_df)?;
+                }
+            }
+
+            let expected = prom_from_polars_with_labels(&data_df, &criteria_df, "id")?;
+            let p = prom_from_file(&path, format, &criteria_df, Some("id"))?;
+            assert_eq!(
+                vec!["id", "one", "skipped", "two", "risk"],
+                column_names(&path, format)?
+            );
+            assert_eq!(expected.labels, p.labels);
+            assert_eq!(expected.matrix_t.shape(), p.matrix_t.shape());
+            assert!(expected
+                .matrix_t
+                .iter()
+                .zip(p.matrix_t.iter())
+                .all(|(a, b)| a == b || (a.is_nan() && b.is_nan())));
+
+            criteria_df.with_column(Series::new("name", &["one", "two", "missing"]))?;
+            assert!(read_matrix_t(&path, format, &criteria_df).is_err());
+
+            Ok(())
+        }
+
+        #[cfg(feature = "ipc")]
+        #[test]
+        fn test_ipc() -> Result<()> {
+            check(ColumnarFormat::Ipc, "arrow")
+        }
+
+        #[cfg(feature = "parquet")]
+        #[test]
+        fn test_parquet() -> Result<()> {
+            check(ColumnarFormat::Parquet, "parquet")
+        }
     }
 }
@@ -0,0 +1,205 @@
+use super::outranking::{has_link_ab, outranking_edges};
+use super::promethee::{Prom, PromResultI, PromResultII};
+use super::types::{Fl, MCDMRSError, Result};
+use ndarray::Array1;
+
+fn not_computed(name: &str) -> MCDMRSError {
+    MCDMRSError::Error(format!("{} has not been computed", name))
+}
+
+impl Prom {
+    /// Returns the label of alternative `i`, or its index when the analysis is unlabeled.
+    pub fn label(&self, i: usize) -> String {
+        match &self.labels {
+            Some(labels) => labels[i].clone(),
+            None => i.to_string(),
+        }
+    }
+
+    /// Returns the name of criterion `j`, or its index when the criteria are unnamed.
+    pub fn criterion_name(&self, j: usize) -> String {
+        match &self.criteria.name {
+            Some(name) => name[j].clone(),
+            None => j.to_string(),
+        }
+    }
+
+    /// Returns the label of every alternative, see [`Prom::label`].
+    pub fn alternative_labels(&self) -> Array1<String> {
+        Array1::from_iter((0..self.matrix_t.dim().1).map(|i| self.label(i)))
+    }
+
+    /// Returns the name of every criterion, see [`Prom::criterion_name`].
+    pub fn criterion_names(&self) -> Array1<String> {
+        Array1::from_iter((0..self.matrix_t.dim().0).map(|j| self.criterion_name(j)))
+    }
+
+    /// Returns the alternative with this label. The labels set by [`Prom::with_labels`]
+    /// are looked up in [`Prom::label_index`], other labels are scanned.
+    pub fn alternative_index(&self, label: &str) -> Result<usize> {
+        let n = self.matrix_t.dim().1;
+        let i = match &self.labels {
+            Some(labels) => self
+                .label_index
+                .as_ref()
+                .and_then(|index| index.get(label).copied())
+                .filter(|&i| labels.get(i).is_some_and(|l| l == label))
+                .or_else(|| labels.iter().position(|l| l == label)),
+            None => label.parse::<usize>().ok().filter(|&i| i < n),
+        };
+        i.ok_or(MCDMRSError::Error(format!("Unknown alternative `{}`", label)).into())
+    }
+
+    pub fn criterion_index(&self, name: &str) -> Result<usize> {
+        let j = match &self.criteria.name {
+            Some(names) => names.iter().position(|n| n == name),
+            None => name
+                .parse::<usize>()
+                .ok()
+                .filter(|&j| j < self.matrix_t.dim().0),
+        };
+        j.ok_or(MCDMRSError::Error(format!("Unknown criterion `{}`", name)).into())
+    }
+
+    fn result_i(&self) -> Result<&PromResultI> {
+        Ok(self.prom_i.as_ref().ok_or(not_computed("Promethee I"))?)
+    }
+
+    pub(crate) fn result_ii(&self) -> Result<&PromResultII> {
+        Ok(self.prom_ii.as_ref().ok_or(not_computed("Promethee II"))?)
+    }
+
+    /// Returns the net flow of the alternative with this label.
+    pub fn score_of(&self, label: &str) -> Result<Fl> {
+        Ok(self.result_ii()?.score[self.alternative_index(label)?])
+    }
+
+    /// Returns the 1-based Promethee II rank of the alternative with this label.
+    pub fn rank_of(&self, label: &str) -> Result<usize> {
+        let i = self.alternative_index(label)?;
+        Ok(self.result_ii()?.ranks()[i])
+    }
+
+    /// Returns the positive and negative outranking flows of the alternative with this label.
+    pub fn flows_of(&self, label: &str) -> Result<(Fl, Fl)> {
+        let i = self.alternative_index(label)?;
+        let pi = self.result_i()?;
+        Ok((pi.phi_plus_score[i], pi.phi_minus_score[i]))
+    }
+
+    /// Returns the weighted net flow of one alternative on one criterion.
+    pub fn criterion_flow_of(&self, label: &str, criterion: &str) -> Result<Fl> {
+        let (i, j) = (
+            self.alternative_index(label)?,
+            self.criterion_index(criterion)?,
+        );
+        Ok(self.result_ii()?.weighted_flow[[i, j]])
+    }
+
+    /// Returns true if `a` outranks `b` in the Promethee I partial ranking.
+    pub fn outranks_by_label(&self, a: &str, b: &str) -> Result<bool> {
+        let (a, b) = (self.alternative_index(a)?, self.alternative_index(b)?);
+        let pi = self.result_i()?;
+        let (pp, pm) = (&pi.phi_plus_score, &pi.phi_minus_score);
+        Ok(has_link_ab(&pp[a], &pm[a], &pp[b], &pm[b]))
+    }
+
+    /// Returns the labeled net flows, in alternative order.
+    pub fn labeled_scores(&self) -> Result<Vec<(String, Fl)>> {
+        let score = &self.result_ii()?.score;
+        Ok(score
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (self.label(i), *s))
+            .collect())
+    }
+
+    /// Returns the Promethee I outranking relations as `(source, target)` label pairs.
+    pub fn labeled_edges(&self) -> Result<Vec<(String, String)>> {
+        let pi = self.result_i()?;
+        Ok(
+            outranking_edges(pi.phi_plus_score.view(), pi.phi_minus_score.view())?
+                .into_iter()
+                .map(|(a, b)| (self.label(a), self.label(b)))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::utils;
+    use super::*;
+    use ndarray::array;
+
+    fn get_prom() -> Prom {
+        let mut p = utils::get_prom();
+        p.criteria.name = Some(array!["cost".to_string(), "area".to_string()]);
+        p.with_labels(array!["a".to_string(), "b".to_string(), "c".to_string()])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_query_by_label() -> Result<()> {
+        let mut p = get_prom();
+        assert!(p.score_of("a").is_err());
+
+        p.compute_prom_ii()?;
+
+        assert_eq!(p.prom_ii.as_ref().unwrap().score[1], p.score_of("b")?);
+        assert_eq!(3, p.rank_of("a")?);
+        assert_eq!(1, p.rank_of("b")?);
+        assert_eq!((0.0, 1.0), p.flows_of("a")?);
+        assert_eq!(-0.5, p.criterion_flow_of("a", "area")?);
+        assert!(p.outranks_by_label("b", "a")?);
+        assert!(!p.outranks_by_label("a", "b")?);
+        assert!(p
+            .labeled_edges()?
+            .contains(&("c".to_string(), "a".to_string())));
+        assert_eq!("c", p.labeled_scores()?[2].0);
+
+        assert!(p.score_of("d").is_err());
+        assert!(p.criterion_flow_of("a", "slope").is_err());
+
+        // labels assigned directly are found without the index
+        p.labels = Some(array!["x".to_string(), "y".to_string(), "a".to_string()]);
+        assert_eq!(2, p.alternative_index("a")?);
+        assert_eq!(0, p.alternative_index("x")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unlabeled() -> Result<()> {
+        let mut p = get_prom();
+        p.labels = None;
+        p.criteria.name = None;
+        p.compute_prom_ii()?;
+
+        assert_eq!(
+            array!["0", "1", "2"].map(|s| s.to_string()),
+            p.alternative_labels()
+        );
+        assert_eq!(1, p.criterion_index("1")?);
+        assert!(p.score_of("b").is_err());
+        assert_eq!(1, p.rank_of("2")?);
+        assert!(p.alternative_index("3").is_err());
+        assert!(p.criterion_index("2").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_labels_length() {
+        assert!(get_prom().with_labels(array!["a".to_string()]).is_err());
+        // labels must identify a single alternative
+        let duplicate = array!["a".to_string(), "b".to_string(), "a".to_string()];
+        assert!(get_prom().with_labels(duplicate).is_err());
+        let empty = array!["a".to_string(), "".to_string(), "c".to_string()];
+        assert!(get_prom().with_labels(empty).is_err());
+        assert!(get_prom()
+            .criteria
+            .with_names(array!["a".to_string()])
+            .is_err());
+    }
+}
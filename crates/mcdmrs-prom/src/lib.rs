@@ -1,18 +1,114 @@
+//! Promethee multi-criteria decision analysis.
+//!
+//! Without the default `std` feature the crate is `no_std` + `alloc` and provides the
+//! preference functions and the unicriterion and multicriterion flows only. Without
+//! the default `parallel` feature the flows run serially and rayon is not a dependency.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+// without std `AnyError` is `MCDMRSError`, so the usual `.into()` is an identity
+#![cfg_attr(not(feature = "std"), allow(clippy::useless_conversion))]
+
+extern crate alloc;
+
+#[cfg(feature = "config")]
+pub mod config;
+#[cfg(feature = "config")]
+pub use config::{FromConfig, ProjectConfig};
+#[cfg(feature = "std")]
+pub mod compare;
+#[cfg(feature = "std")]
+pub use compare::{compare, Comparison};
+pub mod compute;
+pub use compute::ComputeOptions;
+#[cfg(feature = "std")]
+pub mod dominance;
+#[cfg(feature = "std")]
+pub use dominance::{dominance_layers, pareto_front};
+#[cfg(feature = "std")]
+pub mod explain;
+#[cfg(feature = "std")]
+pub mod flowsort;
+#[cfg(feature = "std")]
+pub use flowsort::{flow_sort, FlowSortResult, ProfileKind};
+#[cfg(feature = "std")]
+pub mod group;
+#[cfg(feature = "std")]
+pub use group::{Group, GroupResult};
+#[cfg(feature = "std")]
+pub mod hierarchy;
+#[cfg(feature = "std")]
+pub use hierarchy::{ClusterScores, Hierarchy};
+#[cfg(feature = "std")]
+pub mod imprecise;
+#[cfg(feature = "std")]
+pub use imprecise::{robust_relations, RobustRelations, WeightConstraint};
+#[cfg(feature = "std")]
+pub mod inference;
+#[cfg(feature = "std")]
+pub use inference::{infer_criteria, infer_criteria_with, Inference, Statement};
+#[cfg(feature = "std")]
+mod lp;
+#[cfg(feature = "std")]
 pub mod math;
+#[cfg(feature = "std")]
+pub mod missing;
+#[cfg(feature = "std")]
+pub use missing::{Impute, MissingPolicy};
 mod multicriterion_flow;
-pub use multicriterion_flow::multicriterion_flow;
+pub use multicriterion_flow::{multicriterion_flow, multicriterion_flow_with, MCFlowResult};
+#[cfg(feature = "std")]
+pub mod normalize;
+#[cfg(feature = "std")]
+pub use normalize::{normalize, Normalization};
+#[cfg(feature = "std")]
 pub mod interop;
+#[cfg(feature = "std")]
+mod labels;
 
 #[cfg(feature = "io")]
-pub use interop::polars::{df_from_csv, FromPolars};
+pub use interop::files::{df_from_file, CsvOptions, FileFormat};
+#[cfg(feature = "io")]
+pub use interop::polars::{df_from_csv, missing_report, FromPolars};
 
 mod pref_functions;
+pub use pref_functions::{
+    builtin, is_pref_function, resolve, Level, PiecewiseLinear, PrefFunctions, PreferenceFunction,
+    UShape, Usual, VShape, VShape2, PREF_FUNCTIONS,
+};
+#[cfg(feature = "std")]
 mod promethee;
+#[cfg(feature = "std")]
 pub use promethee::{Criteria, Prom};
+#[cfg(feature = "std")]
+pub mod scale;
+#[cfg(feature = "std")]
+pub use scale::OrdinalScale;
 pub mod types;
 pub use types::{Fl, Result};
 mod cmp;
+#[cfg(feature = "std")]
 mod outranking;
-pub use outranking::outranking_adjacency_matrix;
+#[cfg(feature = "serde")]
+pub mod persist;
+#[cfg(feature = "std")]
+pub use outranking::{outranking_adjacency_matrix, outranking_edges};
+#[cfg(feature = "serde")]
+pub use persist::{SavedProm, FORMAT_VERSION};
+#[cfg(feature = "std")]
+pub mod pairwise;
+#[cfg(feature = "std")]
+pub mod reversal;
+#[cfg(feature = "std")]
+pub use reversal::{RankReversal, Scenario};
+#[cfg(feature = "std")]
+pub mod sensitivity;
+#[cfg(feature = "simd")]
+pub mod simd;
+#[cfg(feature = "std")]
+pub mod thresholds;
 pub mod unicriterion_flow;
+#[cfg(feature = "std")]
 pub mod utils;
+#[cfg(feature = "io")]
+pub mod validation;
+#[cfg(feature = "io")]
+pub use validation::{validate_criteria, ValidationReport};
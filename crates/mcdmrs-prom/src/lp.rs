@@ -0,0 +1,246 @@
+//! A small dense two-phase simplex solver for the linear programs of the robust
+//! analyses. The problems are tiny (one variable per criterion), so the solver favors
+//! simplicity and uses Bland's rule to avoid cycling.
+
+const EPS: f64 = 1e-9;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Relation {
+    Le,
+    Eq,
+    Ge,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Outcome {
+    Optimal { value: f64, x: Vec<f64> },
+    Infeasible,
+    Unbounded,
+}
+
+/// A linear program over `n` non-negative variables.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Lp {
+    pub(crate) n: usize,
+    pub(crate) rows: Vec<(Vec<f64>, Relation, f64)>,
+}
+
+impl Lp {
+    pub(crate) fn new(n: usize) -> Lp {
+        Lp { n, rows: vec![] }
+    }
+
+    /// Adds the constraint `coefficients . x <relation> rhs`.
+    pub(crate) fn constrain(&mut self, coefficients: Vec<f64>, relation: Relation, rhs: f64) {
+        debug_assert_eq!(self.n, coefficients.len());
+        self.rows.push((coefficients, relation, rhs));
+    }
+
+    /// Returns true when some `x` satisfies the constraints.
+    pub(crate) fn is_feasible(&self) -> bool {
+        !matches!(self.minimize(&vec![0.0; self.n]), Outcome::Infeasible)
+    }
+
+    pub(crate) fn maximize(&self, c: &[f64]) -> Outcome {
+        let neg: Vec<f64> = c.iter().map(|v| -v).collect();
+        match self.minimize(&neg) {
+            Outcome::Optimal { value, x } => Outcome::Optimal { value: -value, x },
+            other => other,
+        }
+    }
+
+    /// Minimizes `c . x` subject to the constraints and `x >= 0`.
+    pub(crate) fn minimize(&self, c: &[f64]) -> Outcome {
+        let n = self.n;
+        let n_slack = self
+            .rows
+            .iter()
+            .filter(|(_, r, _)| *r != Relation::Eq)
+            .count();
+        // every row gets an artificial variable so the first basis is trivial
+        let n_art = self.rows.len();
+        let cols = n + n_slack + n_art;
+
+        let mut tableau = Vec::with_capacity(self.rows.len());
+        let mut basis = Vec::with_capacity(self.rows.len());
+        let mut slack = n;
+        for (i, (a, relation, b)) in self.rows.iter().enumerate() {
+            // keep the right-hand sides non-negative
+            let sign = if *b < 0.0 { -1.0 } else { 1.0 };
+            let mut row = vec![0.0; cols + 1];
+            for (r, v) in row.iter_mut().zip(a) {
+                *r = sign * v;
+            }
+            match relation {
+                Relation::Le => {
+                    row[slack] = sign;
+                    slack += 1;
+                }
+                Relation::Ge => {
+                    row[slack] = -sign;
+                    slack += 1;
+                }
+                Relation::Eq => {}
+            }
+            row[n + n_slack + i] = 1.0;
+            row[cols] = sign * b;
+            tableau.push(row);
+            basis.push(n + n_slack + i);
+        }
+
+        // phase 1: minimize the sum of the artificial variables
+        let mut cost = vec![0.0; cols];
+        cost[n + n_slack..].iter_mut().for_each(|v| *v = 1.0);
+        simplex(&mut tableau, &mut basis, &cost, cols);
+        let infeasibility: f64 = basis
+            .iter()
+            .zip(&tableau)
+            .filter(|(&j, _)| j >= n + n_slack)
+            .map(|(_, row)| row[cols])
+            .sum();
+        if infeasibility > EPS.sqrt() {
+            return Outcome::Infeasible;
+        }
+
+        // drive the remaining artificial variables out of the basis, dropping the
+        // redundant rows
+        let mut i = 0;
+        while i < basis.len() {
+            if basis[i] >= n + n_slack {
+                match (0..n + n_slack).find(|&j| tableau[i][j].abs() > EPS) {
+                    Some(j) => pivot(&mut tableau, &mut basis, i, j),
+                    None => {
+                        tableau.remove(i);
+                        basis.remove(i);
+                        continue;
+                    }
+                }
+            }
+            i += 1;
+        }
+
+        // phase 2
+        let mut cost = vec![0.0; cols];
+        cost[..n].copy_from_slice(c);
+        if !simplex(&mut tableau, &mut basis, &cost, n + n_slack) {
+            return Outcome::Unbounded;
+        }
+
+        let mut x = vec![0.0; n];
+        for (&j, row) in basis.iter().zip(&tableau) {
+            if j < n {
+                x[j] = row[cols];
+            }
+        }
+        Outcome::Optimal {
+            value: c.iter().zip(&x).map(|(c, x)| c * x).sum(),
+            x,
+        }
+    }
+}
+
+fn pivot(tableau: &mut [Vec<f64>], basis: &mut [usize], row: usize, col: usize) {
+    let p = tableau[row][col];
+    tableau[row].iter_mut().for_each(|v| *v /= p);
+    let pivot_row = tableau[row].clone();
+    for (i, r) in tableau.iter_mut().enumerate() {
+        let f = r[col];
+        if i != row && f != 0.0 {
+            r.iter_mut().zip(&pivot_row).for_each(|(v, p)| *v -= f * p);
+        }
+    }
+    basis[row] = col;
+}
+
+/// Runs the simplex iterations with the columns before `allowed` as candidates to
+/// enter the basis. Returns false when the objective is unbounded.
+fn simplex(tableau: &mut [Vec<f64>], basis: &mut [usize], cost: &[f64], allowed: usize) -> bool {
+    let rhs = cost.len();
+    loop {
+        // Bland's rule: the first improving column enters
+        let entering = (0..allowed).find(|&j| {
+            !basis.contains(&j) && {
+                let reduced: f64 = cost[j]
+                    - basis
+                        .iter()
+                        .zip(tableau.iter())
+                        .map(|(&b, row)| cost[b] * row[j])
+                        .sum::<f64>();
+                reduced < -EPS
+            }
+        });
+        let Some(j) = entering else {
+            return true;
+        };
+
+        // ... and the row with the smallest ratio, ties broken by the basis index
+        let leaving = (0..tableau.len())
+            .filter(|&i| tableau[i][j] > EPS)
+            .min_by(|&a, &b| {
+                let (ra, rb) = (
+                    tableau[a][rhs] / tableau[a][j],
+                    tableau[b][rhs] / tableau[b][j],
+                );
+                ra.total_cmp(&rb).then(basis[a].cmp(&basis[b]))
+            });
+        match leaving {
+            Some(i) => pivot(tableau, basis, i, j),
+            None => return false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn value(outcome: Outcome) -> f64 {
+        match outcome {
+            Outcome::Optimal { value, .. } => value,
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_minimize() {
+        // max 3x + 2y st x + y <= 4, x + 3y <= 6, x <= 3
+        let mut lp = Lp::new(2);
+        lp.constrain(vec![1., 1.], Relation::Le, 4.);
+        lp.constrain(vec![1., 3.], Relation::Le, 6.);
+        lp.constrain(vec![1., 0.], Relation::Le, 3.);
+        match lp.maximize(&[3., 2.]) {
+            Outcome::Optimal { value, x } => {
+                assert!((value - 11.).abs() < 1e-9);
+                assert!((x[0] - 3.).abs() < 1e-9 && (x[1] - 1.).abs() < 1e-9);
+            }
+            other => panic!("{:?}", other),
+        }
+
+        // weights on the simplex with w0 >= w1 + 0.2 and w2 >= 0.3
+        let mut lp = Lp::new(3);
+        lp.constrain(vec![1., 1., 1.], Relation::Eq, 1.);
+        lp.constrain(vec![1., -1., 0.], Relation::Ge, 0.2);
+        lp.constrain(vec![0., 0., -1.], Relation::Le, -0.3);
+        assert!((value(lp.minimize(&[1., 0., 0.])) - 0.2).abs() < 1e-9);
+        assert!((value(lp.maximize(&[0., 1., 0.])) - 0.25).abs() < 1e-9);
+        assert!((value(lp.maximize(&[0., 0., 1.])) - 0.8).abs() < 1e-9);
+
+        // a redundant equality
+        lp.constrain(vec![2., 2., 2.], Relation::Eq, 2.);
+        assert!((value(lp.minimize(&[1., 0., 0.])) - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_infeasible_unbounded() {
+        let mut lp = Lp::new(2);
+        lp.constrain(vec![1., 1.], Relation::Le, 1.);
+        assert!(lp.is_feasible());
+        lp.constrain(vec![1., 0.], Relation::Ge, 2.);
+        assert!(!lp.is_feasible());
+        assert_eq!(Outcome::Infeasible, lp.minimize(&[1., 0.]));
+
+        let mut lp = Lp::new(2);
+        lp.constrain(vec![1., -1.], Relation::Le, 1.);
+        assert_eq!(Outcome::Unbounded, lp.maximize(&[1., 1.]));
+    }
+}
@@ -1,3 +1,4 @@
+use super::cmp::{gt, lt};
 use super::types::{Fl, Result};
 use ndarray::{Array1, Array2, ArrayView1, ArrayView2};
 
@@ -6,11 +7,13 @@ pub fn mult_axis_0(ndarr: ArrayView2<Fl>, other: ArrayView1<Fl>) -> Result<Array
 }
 
 pub fn min_max_norm(array: ArrayView1<Fl>) -> Array1<Fl> {
-    let _max: Fl = *array.iter().max_by(|a, b| a.total_cmp(b)).unwrap_or(&0.0);
-    let _min: Fl = *array.iter().min_by(|a, b| a.total_cmp(b)).unwrap_or(&0.0);
+    // NaN values are ignored when finding the range and stay NaN.
+    let valid = || array.iter().filter(|v| !v.is_nan());
+    let _max: Fl = *valid().max_by(|a, b| a.total_cmp(b)).unwrap_or(&0.0);
+    let _min: Fl = *valid().min_by(|a, b| a.total_cmp(b)).unwrap_or(&0.0);
     let range = _max - _min;
     if range.abs() < 1e-7 {
-        Array1::ones(array.len())
+        array.mapv(|v| if v.is_nan() { v } else { 1.0 })
     } else {
         (&array - _min) / range
     }
@@ -27,6 +30,50 @@ pub fn normalize_vec(array: ArrayView1<Fl>) -> Array1<Fl> {
     }
 }
 
+pub fn quantile(array: ArrayView1<Fl>, q: Fl) -> Fl {
+    // linearly interpolated quantile of the non-NaN values, or NaN if there are none.
+    let mut values: Vec<Fl> = array.iter().copied().filter(|v| !v.is_nan()).collect();
+    if values.is_empty() {
+        return Fl::NAN;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+
+    let pos = q.clamp(0.0, 1.0) * (values.len() - 1) as Fl;
+    let (lo, hi) = (pos.floor() as usize, pos.ceil() as usize);
+    values[lo] + (values[hi] - values[lo]) * (pos - lo as Fl)
+}
+
+pub fn rank(array: ArrayView1<Fl>) -> Array1<usize> {
+    // 1-based descending rank. Values within tolerance share the best rank of their
+    // group (i.e., 1, 2, 2, 4) and NaN values are ranked last.
+    let mut order: Vec<usize> = (0..array.len()).collect();
+    order.sort_by(|&a, &b| match (array[a].is_nan(), array[b].is_nan()) {
+        (false, false) => array[b].total_cmp(&array[a]),
+        (a_nan, b_nan) => a_nan.cmp(&b_nan),
+    });
+
+    let mut ranks = Array1::<usize>::zeros(array.len());
+    for (pos, &i) in order.iter().enumerate() {
+        ranks[i] = match pos {
+            0 => 1,
+            _ => {
+                let prev = order[pos - 1];
+                let (a, b) = (&array[prev], &array[i]);
+                let tied = match (a.is_nan(), b.is_nan()) {
+                    (false, false) => !gt(a, b) && !lt(a, b),
+                    (a_nan, b_nan) => a_nan && b_nan,
+                };
+                if tied {
+                    ranks[prev]
+                } else {
+                    pos + 1
+                }
+            }
+        };
+    }
+    ranks
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -92,4 +139,26 @@ mod tests {
         assert_eq!(array![0., 0.0, 1.], normalize_vec(vec.view()));
         assert_eq!(one, normalize_vec(vec.view()).iter().sum::<Fl>());
     }
+
+    #[test]
+    fn test_quantile() {
+        let vec = array![3., 1., Fl::NAN, 2., 4.];
+        assert_eq!(2.5, quantile(vec.view(), 0.5));
+        assert_eq!(1.0, quantile(vec.view(), 0.0));
+        assert_eq!(4.0, quantile(vec.view(), 1.0));
+        assert_eq!(1.75, quantile(vec.view(), 0.25));
+        assert!(quantile(array![Fl::NAN].view(), 0.5).is_nan());
+    }
+
+    #[test]
+    fn test_rank() {
+        let vec = array![0.2, 0.9, -0.4, 0.2];
+        assert_eq!(array![2, 1, 4, 2], rank(vec.view()));
+
+        let vec = array![0.3 - 0.1, 0.2, Fl::NAN, 0.5];
+        assert_eq!(array![2, 2, 4, 1], rank(vec.view()));
+
+        let vec: Array1<Fl> = array![];
+        assert_eq!(Array1::<usize>::zeros(0), rank(vec.view()));
+    }
 }
@@ -0,0 +1,330 @@
+use super::compute::ComputeOptions;
+use super::math::{mult_axis_0, quantile};
+use super::multicriterion_flow::{multicriterion_flow_with, MCFlowResult};
+use super::pref_functions::PrefFunctions;
+use super::promethee::Prom;
+use super::types::{Fl, MCDMRSError, Result};
+use ndarray::{Array1, Array2, ArrayView1, ArrayView2, Axis, CowArray, Ix2};
+use std::fmt;
+use std::str::FromStr;
+
+/// The value substituted for a missing entry by [`MissingPolicy::Impute`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Impute {
+    Mean,
+    Median,
+    /// The least preferred observed value, taking the criterion direction into account.
+    Worst,
+    /// The most preferred observed value, taking the criterion direction into account.
+    Best,
+}
+
+/// How missing (NaN) values are handled when computing the preference flows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum MissingPolicy {
+    /// Comparisons involving a missing value count as indifference, and flows are
+    /// still divided by `n - 1`.
+    #[default]
+    Skip,
+    /// Flows on each criterion are divided by the number of valid comparisons
+    /// (`k - 1` for `k` alternatives with a value) instead of `n - 1`.
+    Valid,
+    /// Missing values are replaced before computing the flows.
+    Impute(Impute),
+    /// Alternatives with any missing value are left out of the comparison and
+    /// receive NaN flows and scores.
+    Exclude,
+}
+
+impl FromStr for MissingPolicy {
+    type Err = MCDMRSError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(MissingPolicy::Skip),
+            "valid" => Ok(MissingPolicy::Valid),
+            "mean" => Ok(MissingPolicy::Impute(Impute::Mean)),
+            "median" => Ok(MissingPolicy::Impute(Impute::Median)),
+            "worst" => Ok(MissingPolicy::Impute(Impute::Worst)),
+            "best" => Ok(MissingPolicy::Impute(Impute::Best)),
+            "exclude" => Ok(MissingPolicy::Exclude),
+            _ => Err(MCDMRSError::Error(format!(
+                "invalid missing value policy `{}`, expected one of \
+                 skip, valid, mean, median, worst, best or exclude",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for MissingPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            MissingPolicy::Skip => "skip",
+            MissingPolicy::Valid => "valid",
+            MissingPolicy::Impute(Impute::Mean) => "mean",
+            MissingPolicy::Impute(Impute::Median) => "median",
+            MissingPolicy::Impute(Impute::Worst) => "worst",
+            MissingPolicy::Impute(Impute::Best) => "best",
+            MissingPolicy::Exclude => "exclude",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Returns the number of missing values on each criterion.
+pub fn missing_counts(matrix_t: ArrayView2<Fl>) -> Array1<usize> {
+    matrix_t.map_axis(Axis(1), |row| row.iter().filter(|v| v.is_nan()).count())
+}
+
+/// Returns true for each alternative that has a value on every criterion.
+pub fn complete_alternatives(matrix_t: ArrayView2<Fl>) -> Array1<bool> {
+    matrix_t.map_axis(Axis(0), |col| col.iter().all(|v| !v.is_nan()))
+}
+
+fn impute_value(row: ArrayView1<Fl>, how: Impute) -> Fl {
+    let valid = row.iter().copied().filter(|v| !v.is_nan());
+    match how {
+        Impute::Mean => {
+            let (sum, count) = valid.fold((0.0, 0), |(s, c), v| (s + v, c + 1));
+            if count == 0 {
+                Fl::NAN
+            } else {
+                sum / count as Fl
+            }
+        }
+        Impute::Median => quantile(row, 0.5),
+        Impute::Worst => valid.reduce(Fl::min).unwrap_or(Fl::NAN),
+        Impute::Best => valid.reduce(Fl::max).unwrap_or(Fl::NAN),
+    }
+}
+
+/// Replaces the missing values of each criterion.
+///
+/// `matrix_t` must already be multiplied by the criteria types, so that larger values
+/// are preferred on every criterion.
+pub fn impute(matrix_t: ArrayView2<Fl>, how: Impute) -> Array2<Fl> {
+    let mut out = matrix_t.to_owned();
+    for mut row in out.axis_iter_mut(Axis(0)) {
+        let value = impute_value(row.view(), how);
+        row.iter_mut()
+            .filter(|v| v.is_nan())
+            .for_each(|v| *v = value);
+    }
+    out
+}
+
+/// Computes the unicriterion flows of each criterion under a missing value policy.
+///
+/// `matrix_t` must already be multiplied by the criteria types.
+pub fn multicriterion_flow_with_policy(
+    matrix_t: ArrayView2<Fl>,
+    pref_function: ArrayView1<String>,
+    q: ArrayView1<Fl>,
+    p: ArrayView1<Fl>,
+    functions: &PrefFunctions,
+    policy: MissingPolicy,
+    options: &ComputeOptions,
+) -> Result<MCFlowResult> {
+    let multicriterion_flow = |matrix_t: ArrayView2<Fl>| {
+        multicriterion_flow_with(matrix_t, pref_function, q, p, functions, options)
+    };
+    match policy {
+        MissingPolicy::Skip => multicriterion_flow(matrix_t),
+        MissingPolicy::Impute(how) => multicriterion_flow(impute(matrix_t, how).view()),
+        MissingPolicy::Valid => {
+            let mut mc = multicriterion_flow(matrix_t)?;
+            let n = matrix_t.dim().1 as Fl;
+            for (j, count) in missing_counts(matrix_t).iter().enumerate() {
+                let k = n - *count as Fl;
+                let scale = if k > 1.0 { (n - 1.0) / (k - 1.0) } else { 0.0 };
+                mc.pref_matrix_plus_t.row_mut(j).mapv_inplace(|v| v * scale);
+                mc.pref_matrix_minus_t
+                    .row_mut(j)
+                    .mapv_inplace(|v| v * scale);
+            }
+            Ok(mc)
+        }
+        MissingPolicy::Exclude => {
+            let keep: Vec<usize> = complete_alternatives(matrix_t)
+                .iter()
+                .enumerate()
+                .filter_map(|(i, &complete)| complete.then_some(i))
+                .collect();
+            if keep.len() < 2 {
+                return Err(MCDMRSError::Error(
+                    "Excluding incomplete alternatives leaves fewer than two to compare"
+                        .to_string(),
+                )
+                .into());
+            }
+
+            let subset = multicriterion_flow(matrix_t.select(Axis(1), &keep).view())?;
+            let mut mc = MCFlowResult {
+                pref_matrix_plus_t: Array2::from_elem(matrix_t.dim(), Fl::NAN),
+                pref_matrix_minus_t: Array2::from_elem(matrix_t.dim(), Fl::NAN),
+            };
+            for (s, &i) in keep.iter().enumerate() {
+                mc.pref_matrix_plus_t
+                    .column_mut(i)
+                    .assign(&subset.pref_matrix_plus_t.column(s));
+                mc.pref_matrix_minus_t
+                    .column_mut(i)
+                    .assign(&subset.pref_matrix_minus_t.column(s));
+            }
+            Ok(mc)
+        }
+    }
+}
+
+impl Prom {
+    /// Sets the missing value policy, discarding any previously computed flows.
+    pub fn with_missing_policy(mut self, missing: MissingPolicy) -> Prom {
+        self.missing = missing;
+        self.mc_flow = None;
+        self.prom_i = None;
+        self.prom_ii = None;
+        self.pi = None;
+        self
+    }
+
+    /// Returns the number of missing values on each criterion.
+    pub fn missing_counts(&self) -> Array1<usize> {
+        missing_counts(self.matrix_t.view())
+    }
+
+    /// Returns `matrix_t` as the pairwise analyses see it under the missing value
+    /// policy: imputed under `Impute`, as is under `Skip`, where comparisons with a
+    /// missing value are indifferent. `Valid` and `Exclude` only define the flows, so
+    /// `analysis` is reported as not supporting them.
+    pub(crate) fn policy_matrix_t(&self, analysis: &str) -> Result<CowArray<'_, Fl, Ix2>> {
+        match self.missing {
+            MissingPolicy::Skip => Ok(self.matrix_t.view().into()),
+            MissingPolicy::Impute(how) => {
+                // the criterion types are 1 or -1, so multiplying twice restores the values
+                let t = self.criteria.criteria_type.view();
+                let mat = mult_axis_0(self.matrix_t.view(), t)?;
+                Ok(mult_axis_0(impute(mat.view(), how).view(), t)?.into())
+            }
+            MissingPolicy::Valid | MissingPolicy::Exclude => Err(MCDMRSError::Error(format!(
+                "{} does not support the `{}` missing value policy",
+                analysis, self.missing
+            ))
+            .into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::utils::test_prom;
+    use super::*;
+    use is_close::all_close;
+    use ndarray::array;
+
+    fn get_prom(missing: MissingPolicy) -> Prom {
+        test_prom(
+            array![[0.8, Fl::NAN, 0.05, 0.3], [0.1, 0.6, 0.4, 0.2]],
+            array![-1., 1.],
+        )
+        .with_missing_policy(missing)
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        for s in [
+            "skip", "valid", "mean", "median", "worst", "best", "exclude",
+        ] {
+            let policy: MissingPolicy = s.parse().unwrap();
+            assert_eq!(s, policy.to_string());
+        }
+        assert!("zero".parse::<MissingPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_impute() {
+        let mat = array![[1., Fl::NAN, 3., 8.], [Fl::NAN, Fl::NAN, Fl::NAN, Fl::NAN]];
+        assert_eq!(4.0, impute(mat.view(), Impute::Mean)[[0, 1]]);
+        assert_eq!(3.0, impute(mat.view(), Impute::Median)[[0, 1]]);
+        assert_eq!(1.0, impute(mat.view(), Impute::Worst)[[0, 1]]);
+        assert_eq!(8.0, impute(mat.view(), Impute::Best)[[0, 1]]);
+        assert!(impute(mat.view(), Impute::Mean)[[1, 0]].is_nan());
+    }
+
+    #[test]
+    fn test_missing_counts() {
+        let p = get_prom(MissingPolicy::Skip);
+        assert_eq!(array![1, 0], p.missing_counts());
+        assert_eq!(
+            array![true, false, true, true],
+            complete_alternatives(p.matrix_t.view())
+        );
+    }
+
+    #[test]
+    fn test_valid_rescales_flows() -> Result<()> {
+        let mut skip = get_prom(MissingPolicy::Skip);
+        let mut valid = get_prom(MissingPolicy::Valid);
+        skip.compute_multicriterion_flow()?;
+        valid.compute_multicriterion_flow()?;
+
+        let (s, v) = (skip.mc_flow.unwrap(), valid.mc_flow.unwrap());
+        // 3 valid values on the first criterion, so flows are scaled by 3 / 2
+        assert!(all_close!(
+            (&s.pref_matrix_plus_t.row(0) * 1.5).to_vec(),
+            v.pref_matrix_plus_t.row(0).to_vec()
+        ));
+        assert_eq!(s.pref_matrix_plus_t.row(1), v.pref_matrix_plus_t.row(1));
+        // the best alternative on a criterion with missing values still gets a full flow
+        assert_eq!(1.0, v.pref_matrix_plus_t[[0, 2]]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_impute_uses_direction() -> Result<()> {
+        // the first criterion is minimized, so its worst value is the largest one
+        let mut p = get_prom(MissingPolicy::Impute(Impute::Worst));
+        p.compute_multicriterion_flow()?;
+        let mc = p.mc_flow.unwrap();
+        assert_eq!(0.0, mc.pref_matrix_plus_t[[0, 1]]);
+        assert_eq!(0.0, mc.pref_matrix_plus_t[[0, 0]]);
+
+        let mut p = get_prom(MissingPolicy::Impute(Impute::Best));
+        p.compute_multicriterion_flow()?;
+        let mc = p.mc_flow.unwrap();
+        assert_eq!(mc.pref_matrix_plus_t[[0, 1]], mc.pref_matrix_plus_t[[0, 2]]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_exclude() -> Result<()> {
+        let mut p = get_prom(MissingPolicy::Exclude);
+        p.compute_prom_ii()?;
+
+        let pii = p.prom_ii.unwrap();
+        assert!(pii.score[1].is_nan());
+        assert!(pii.normalized_score[1].is_nan());
+
+        // the remaining alternatives are scored as if the excluded one did not exist
+        let mut subset = Prom::new(p.matrix_t.select(Axis(1), &[0, 2, 3]), p.criteria.clone())?;
+        subset.compute_prom_ii()?;
+        let expected = subset.prom_ii.unwrap().score;
+        assert!(all_close!(
+            expected.to_vec(),
+            vec![pii.score[0], pii.score[2], pii.score[3]]
+        ));
+
+        let mut p = get_prom(MissingPolicy::Exclude);
+        p.matrix_t[[1, 0]] = Fl::NAN;
+        p.matrix_t[[1, 2]] = Fl::NAN;
+        assert!(p.compute_prom_ii().is_err());
+
+        Ok(())
+    }
+}
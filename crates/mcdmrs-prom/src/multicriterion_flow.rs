@@ -1,11 +1,15 @@
+use super::compute::{zip_for_each, ComputeOptions};
+use super::pref_functions::{resolve, PrefFunctions};
 use super::types::{Fl, Result};
 use super::unicriterion_flow::{
-    unicriterion_flow_level, unicriterion_flow_ushape, unicriterion_flow_usual,
-    unicriterion_flow_vshape, unicriterion_flow_vshape2,
+    unicriterion_flow_dyn, unicriterion_flow_level_with, unicriterion_flow_ushape_with,
+    unicriterion_flow_usual_with, unicriterion_flow_vshape2_with, unicriterion_flow_vshape_with,
 };
-use ndarray::{Array2, ArrayView1, ArrayView2, Axis, Zip};
+use alloc::string::String;
+use ndarray::{Array2, ArrayView1, ArrayView2, ArrayViewMut1, Axis, Zip};
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MCFlowResult {
     pub pref_matrix_plus_t: Array2<Fl>,
     pub pref_matrix_minus_t: Array2<Fl>,
@@ -16,34 +20,69 @@ pub fn multicriterion_flow(
     pref_function: ArrayView1<String>,
     q: ArrayView1<Fl>,
     p: ArrayView1<Fl>,
+) -> Result<MCFlowResult> {
+    multicriterion_flow_with(
+        matrix_t,
+        pref_function,
+        q,
+        p,
+        &PrefFunctions::new(),
+        &ComputeOptions::Global,
+    )
+}
+
+/// Like [`multicriterion_flow`], but preference function names that are not built in
+/// are looked up in `functions`, and the kernels run as set by `options`.
+pub fn multicriterion_flow_with(
+    matrix_t: ArrayView2<Fl>,
+    pref_function: ArrayView1<String>,
+    q: ArrayView1<Fl>,
+    p: ArrayView1<Fl>,
+    functions: &PrefFunctions,
+    options: &ComputeOptions,
 ) -> Result<MCFlowResult> {
     let (m, n) = matrix_t.dim();
     assert!(
         m == pref_function.len() && m == q.len() && m == p.len(),
         "Inputs must be of same length"
     );
+    for name in pref_function.iter() {
+        resolve(name, functions)?;
+    }
     let mut pref_matrix_plus_t: Array2<Fl> = Array2::zeros((m, n));
     let mut pref_matrix_minus_t: Array2<Fl> = Array2::zeros((m, n));
 
-    Zip::from(matrix_t.axis_iter(Axis(0)))
-        .and(pref_matrix_plus_t.axis_iter_mut(Axis(0)))
-        .and(pref_matrix_minus_t.axis_iter_mut(Axis(0)))
-        .and(pref_function)
-        .and(q)
-        .and(p)
-        .par_for_each(|col, mut ppt, mut pmt, pref, q, p| {
+    zip_for_each!(
+        options,
+        Zip::from(matrix_t.axis_iter(Axis(0)))
+            .and(pref_matrix_plus_t.axis_iter_mut(Axis(0)))
+            .and(pref_matrix_minus_t.axis_iter_mut(Axis(0)))
+            .and(pref_function)
+            .and(q)
+            .and(p),
+        |col: ArrayView1<Fl>,
+         mut ppt: ArrayViewMut1<Fl>,
+         mut pmt: ArrayViewMut1<Fl>,
+         pref: &String,
+         q: &Fl,
+         p: &Fl| {
+            let (plus, minus) = (ppt.view_mut(), pmt.view_mut());
             // modify preference matrices in place
             match pref.as_str() {
-                "usual" => unicriterion_flow_usual(col, ppt.view_mut(), pmt.view_mut(), q, p),
-                "ushape" => unicriterion_flow_ushape(col, ppt.view_mut(), pmt.view_mut(), q, p),
-                "vshape" => unicriterion_flow_vshape(col, ppt.view_mut(), pmt.view_mut(), q, p),
-                "vshape2" => unicriterion_flow_vshape2(col, ppt.view_mut(), pmt.view_mut(), q, p),
-                "vshape_2" => unicriterion_flow_vshape2(col, ppt.view_mut(), pmt.view_mut(), q, p),
-                "linear" => unicriterion_flow_vshape2(col, ppt.view_mut(), pmt.view_mut(), q, p),
-                "level" => unicriterion_flow_level(col, ppt.view_mut(), pmt.view_mut(), q, p),
-                _ => panic!("invalid preference function: {:?}", pref),
+                "usual" => unicriterion_flow_usual_with(col, plus, minus, q, p, options),
+                "ushape" => unicriterion_flow_ushape_with(col, plus, minus, q, p, options),
+                "vshape" => unicriterion_flow_vshape_with(col, plus, minus, q, p, options),
+                "vshape2" | "vshape_2" | "linear" => {
+                    unicriterion_flow_vshape2_with(col, plus, minus, q, p, options)
+                }
+                "level" => unicriterion_flow_level_with(col, plus, minus, q, p, options),
+                _ => match functions.get(pref) {
+                    Some(f) => unicriterion_flow_dyn(col, plus, minus, f.as_ref(), q, p, options),
+                    None => unreachable!("invalid preference function: {:?}", pref),
+                },
             }
-        });
+        }
+    );
 
     Ok(MCFlowResult {
         pref_matrix_plus_t,
@@ -69,14 +108,58 @@ mod test {
     use ndarray::array;
 
     #[test]
-    #[should_panic(expected = "invalid preference function")]
-    fn test_mc_panic() {
+    fn test_mc_invalid_function() {
         let array = array![[0.8, 0.2, 0.5], [0.5, 0.8, 0.2]]; // array
         let func_names = array!["usual".to_string(), "panic!".to_string()]; // func
         let q = array![0., 0.]; // q
         let p = array![0., 0.]; // p
 
-        _ = multicriterion_flow(array.view(), func_names.view(), q.view(), p.view());
+        let err = multicriterion_flow(array.view(), func_names.view(), q.view(), p.view());
+        assert!(err
+            .unwrap_err()
+            .to_string()
+            .contains("invalid preference function"));
+    }
+
+    #[test]
+    fn test_custom_pref_function() -> Result<()> {
+        use super::super::pref_functions::{PiecewiseLinear, VShape2};
+        use std::sync::Arc;
+
+        let array = array![[0.8, 0.2, 0.5, Fl::NAN], [0.8, 0.2, 0.5, Fl::NAN]];
+        let q = array![0.1, 0.1];
+        let p = array![0.4, 0.4];
+        let builtin = multicriterion_flow(
+            array.view(),
+            array!["vshape2".to_string(), "vshape2".to_string()].view(),
+            q.view(),
+            p.view(),
+        )?;
+
+        // the same curve as a trait object and as breakpoints
+        let mut functions = PrefFunctions::new();
+        functions.insert("dyn".to_string(), Arc::new(VShape2));
+        functions.insert(
+            "survey".to_string(),
+            Arc::new(PiecewiseLinear::new(vec![(0.1, 0.0), (0.4, 1.0)])?),
+        );
+        let custom = multicriterion_flow_with(
+            array.view(),
+            array!["dyn".to_string(), "survey".to_string()].view(),
+            q.view(),
+            p.view(),
+            &functions,
+            &ComputeOptions::Global,
+        )?;
+
+        for (a, b) in builtin
+            .pref_matrix_plus_t
+            .iter()
+            .zip(custom.pref_matrix_plus_t.iter())
+        {
+            assert!((a - b).abs() < 1e-6);
+        }
+        Ok(())
     }
 
     #[test]
@@ -0,0 +1,373 @@
+use super::math::min_max_norm;
+use super::promethee::Prom;
+use super::types::{Fl, MCDMRSError, Result};
+use ndarray::{Array1, Array2, ArrayView1, ArrayView2, Axis, Zip};
+use std::str::FromStr;
+
+/// How [`normalize`] rescales each criterion.
+///
+/// Every method orients the result so that larger is better: cost criteria (negative
+/// `criteria_type`) are inverted. NaN values are ignored when computing the statistics
+/// and stay NaN.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Normalization {
+    /// `(x - min) / (max - min)`, or `(max - x) / (max - min)` for costs.
+    MinMax,
+    /// `x / ||x||`, or `1 - x / ||x||` for costs.
+    Vector,
+    /// `x / max`, or `1 - x / max` for costs. Values must be non-negative.
+    Max,
+    /// `x / sum`, or `(1 / x) / sum(1 / x)` for costs. Values must be positive for
+    /// costs and non-negative otherwise.
+    Sum,
+    /// `(x - mean) / std`, negated for costs.
+    ZScore,
+    /// `ln x / sum(ln x)`, or `(1 - ln x / sum(ln x)) / (n - 1)` for costs. Values must
+    /// be positive with a product above 1, so that `sum(ln x)` is positive.
+    Log,
+    /// `1 - |x - target| / range`, the closeness to one target value per criterion,
+    /// where the range spans the values and the target. The direction is ignored.
+    Target(Vec<Fl>),
+}
+
+impl FromStr for Normalization {
+    type Err = MCDMRSError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "minmax" => Ok(Normalization::MinMax),
+            "vector" => Ok(Normalization::Vector),
+            "max" => Ok(Normalization::Max),
+            "sum" => Ok(Normalization::Sum),
+            "zscore" => Ok(Normalization::ZScore),
+            "log" => Ok(Normalization::Log),
+            _ => Err(MCDMRSError::Error(format!(
+                "invalid normalization `{}`, expected one of \
+                 minmax, vector, max, sum, zscore or log",
+                s
+            ))),
+        }
+    }
+}
+
+fn valid<'a>(x: &'a ArrayView1<Fl>) -> impl Iterator<Item = Fl> + 'a {
+    x.iter().copied().filter(|v| !v.is_nan())
+}
+
+fn out_of_domain(j: usize, what: &str) -> MCDMRSError {
+    MCDMRSError::Error(format!(
+        "Criterion {} must have {} values for this normalization",
+        j, what
+    ))
+}
+
+/// Normalizes criterion `j` and returns the result with the factor the values were
+/// scaled by, or `None` when the transformation is not linear.
+fn normalize_criterion(
+    x: ArrayView1<Fl>,
+    j: usize,
+    cost: bool,
+    how: &Normalization,
+) -> Result<(Array1<Fl>, Option<Fl>)> {
+    let divide = |d: Fl| if d.abs() < 1e-7 { 0.0 } else { 1.0 / d };
+
+    Ok(match how {
+        Normalization::MinMax => {
+            let max = valid(&x).fold(Fl::NEG_INFINITY, Fl::max);
+            let min = valid(&x).fold(Fl::INFINITY, Fl::min);
+            let y = min_max_norm(x);
+            if cost {
+                (y.mapv(|v| 1.0 - v), Some(divide(max - min)))
+            } else {
+                (y, Some(divide(max - min)))
+            }
+        }
+        Normalization::Vector => {
+            let s = divide(valid(&x).map(|v| v * v).sum::<Fl>().sqrt());
+            if cost {
+                (x.mapv(|v| 1.0 - v * s), Some(s))
+            } else {
+                (x.mapv(|v| v * s), Some(s))
+            }
+        }
+        Normalization::Max => {
+            if valid(&x).any(|v| v < 0.0) {
+                return Err(out_of_domain(j, "non-negative").into());
+            }
+            let s = divide(valid(&x).fold(0.0, Fl::max));
+            if cost {
+                (x.mapv(|v| 1.0 - v * s), Some(s))
+            } else {
+                (x.mapv(|v| v * s), Some(s))
+            }
+        }
+        Normalization::Sum if cost => {
+            if valid(&x).any(|v| v <= 0.0) {
+                return Err(out_of_domain(j, "positive").into());
+            }
+            let s = divide(valid(&x).map(|v| 1.0 / v).sum());
+            (x.mapv(|v| s / v), None)
+        }
+        Normalization::Sum => {
+            if valid(&x).any(|v| v < 0.0) {
+                return Err(out_of_domain(j, "non-negative").into());
+            }
+            let s = divide(valid(&x).sum());
+            (x.mapv(|v| v * s), Some(s))
+        }
+        Normalization::ZScore => {
+            let k = valid(&x).count() as Fl;
+            let mean = valid(&x).sum::<Fl>() / k;
+            let std = (valid(&x).map(|v| (v - mean).powi(2)).sum::<Fl>() / k).sqrt();
+            let s = if cost { -divide(std) } else { divide(std) };
+            (x.mapv(|v| (v - mean) * s), Some(s.abs()))
+        }
+        Normalization::Log => {
+            if valid(&x).any(|v| v <= 0.0) {
+                return Err(out_of_domain(j, "positive").into());
+            }
+            // a sum of logarithms at or below zero would flip or flatten the order
+            let sum: Fl = valid(&x).map(Fl::ln).sum();
+            if sum < 1e-7 {
+                return Err(MCDMRSError::Error(format!(
+                    "Criterion {} must have values whose product exceeds 1 for the log \
+                     normalization",
+                    j
+                ))
+                .into());
+            }
+            let s = 1.0 / sum;
+            let y = x.mapv(|v| v.ln() * s);
+            if cost {
+                let k = (valid(&x).count() as Fl - 1.0).max(1.0);
+                (y.mapv(|v| (1.0 - v) / k), None)
+            } else {
+                (y, None)
+            }
+        }
+        Normalization::Target(targets) => {
+            let t = targets[j];
+            let max = valid(&x).fold(t, Fl::max);
+            let min = valid(&x).fold(t, Fl::min);
+            let s = divide(max - min);
+            (x.mapv(|v| 1.0 - (v - t).abs() * s), Some(s))
+        }
+    })
+}
+
+fn normalize_with_scale(
+    matrix_t: ArrayView2<Fl>,
+    criteria_type: ArrayView1<Fl>,
+    how: &Normalization,
+) -> Result<(Array2<Fl>, Vec<Option<Fl>>)> {
+    let m = matrix_t.dim().0;
+    if m != criteria_type.len() {
+        return Err(MCDMRSError::Error(
+            "The 0 dimension of `matrix_t` must be of same length as `criteria_type`".to_string(),
+        )
+        .into());
+    }
+    if let Normalization::Target(targets) = how {
+        if targets.len() != m || targets.iter().any(|t| t.is_nan()) {
+            return Err(
+                MCDMRSError::Error("There must be one target per criterion".to_string()).into(),
+            );
+        }
+    }
+
+    let mut out = Array2::<Fl>::zeros(matrix_t.dim());
+    let mut scale = Vec::with_capacity(m);
+    for (j, (x, mut row)) in matrix_t
+        .axis_iter(Axis(0))
+        .zip(out.axis_iter_mut(Axis(0)))
+        .enumerate()
+    {
+        let (y, s) = normalize_criterion(x, j, criteria_type[j] < 0.0, how)?;
+        row.assign(&y);
+        scale.push(s);
+    }
+    Ok((out, scale))
+}
+
+/// Normalizes each criterion (row) of `matrix_t` so that larger is better, see
+/// [`Normalization`].
+pub fn normalize(
+    matrix_t: ArrayView2<Fl>,
+    criteria_type: ArrayView1<Fl>,
+    how: &Normalization,
+) -> Result<Array2<Fl>> {
+    Ok(normalize_with_scale(matrix_t, criteria_type, how)?.0)
+}
+
+impl Prom {
+    /// Returns the normalized performance matrix, see [`normalize`].
+    pub fn normalized(&self, how: &Normalization) -> Result<Array2<Fl>> {
+        normalize(
+            self.matrix_t.view(),
+            self.criteria.criteria_type.view(),
+            how,
+        )
+    }
+
+    /// Replaces `matrix_t` by its normalized values before the flows are computed.
+    ///
+    /// All criteria become benefits, and the thresholds `q` and `p` are rescaled with
+    /// the data. Normalizations that are not linear on a criterion, such as `Log` or
+    /// `Sum` on a cost, are only allowed when its thresholds are zero.
+    pub fn normalize(&mut self, how: &Normalization) -> Result<()> {
+        let (matrix_t, scale) = normalize_with_scale(
+            self.matrix_t.view(),
+            self.criteria.criteria_type.view(),
+            how,
+        )?;
+
+        for (j, s) in scale.iter().enumerate() {
+            if s.is_none() && (self.criteria.q[j] != 0.0 || self.criteria.p[j] != 0.0) {
+                return Err(MCDMRSError::Error(format!(
+                    "Criterion {} has preference thresholds, which a non-linear \
+                     normalization cannot rescale",
+                    j
+                ))
+                .into());
+            }
+        }
+
+        let scale = Array1::from_iter(scale.into_iter().map(|s| s.unwrap_or(1.0)));
+        Zip::from(&mut self.criteria.q)
+            .and(&mut self.criteria.p)
+            .and(&scale)
+            .for_each(|q, p, s| {
+                *q *= s;
+                *p *= s;
+            });
+        self.criteria.criteria_type.fill(1.0);
+        self.matrix_t = matrix_t;
+        self.mc_flow = None;
+        self.prom_i = None;
+        self.prom_ii = None;
+        self.pi = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::promethee::Criteria;
+    use super::*;
+    use ndarray::array;
+
+    fn close(a: Array1<Fl>, b: Array1<Fl>) -> bool {
+        a.iter()
+            .zip(b.iter())
+            .all(|(a, b)| (a.is_nan() && b.is_nan()) || (a - b).abs() < 1e-5)
+    }
+
+    #[test]
+    fn test_normalizations() -> Result<()> {
+        let m = array![[1., 2., 4., Fl::NAN], [1., 2., 4., Fl::NAN]];
+        let t = array![1., -1.];
+        let norm = |how| normalize(m.view(), t.view(), &how);
+
+        let y = norm(Normalization::MinMax)?;
+        assert!(close(y.row(0).to_owned(), array![0., 1. / 3., 1., Fl::NAN]));
+        assert!(close(y.row(1).to_owned(), array![1., 2. / 3., 0., Fl::NAN]));
+
+        let y = norm(Normalization::Vector)?;
+        let l2 = (21.0 as Fl).sqrt();
+        assert!(close(
+            y.row(0).to_owned(),
+            array![1. / l2, 2. / l2, 4. / l2, Fl::NAN]
+        ));
+        assert!(close(
+            y.row(1).to_owned(),
+            array![1. - 1. / l2, 1. - 2. / l2, 1. - 4. / l2, Fl::NAN]
+        ));
+
+        let y = norm(Normalization::Max)?;
+        assert!(close(y.row(0).to_owned(), array![0.25, 0.5, 1., Fl::NAN]));
+        assert!(close(y.row(1).to_owned(), array![0.75, 0.5, 0., Fl::NAN]));
+
+        let y = norm(Normalization::Sum)?;
+        assert!(close(
+            y.row(0).to_owned(),
+            array![1. / 7., 2. / 7., 4. / 7., Fl::NAN]
+        ));
+        assert!(close(
+            y.row(1).to_owned(),
+            array![4. / 7., 2. / 7., 1. / 7., Fl::NAN]
+        ));
+
+        let y = norm(Normalization::ZScore)?;
+        assert!(y.row(0).iter().take(3).sum::<Fl>().abs() < 1e-5);
+        assert!(close(y.row(1).to_owned(), -&y.row(0)));
+
+        let y = norm(Normalization::Log)?;
+        assert!(close(
+            y.row(0).to_owned(),
+            array![0., 1. / 3., 2. / 3., Fl::NAN]
+        ));
+        assert!(close(
+            y.row(1).to_owned(),
+            array![0.5, 1. / 3., 1. / 6., Fl::NAN]
+        ));
+
+        let y = norm(Normalization::Target(vec![2., 0.]))?;
+        assert!(close(
+            y.row(0).to_owned(),
+            array![2. / 3., 1., 1. / 3., Fl::NAN]
+        ));
+        assert!(close(y.row(1).to_owned(), array![0.75, 0.5, 0., Fl::NAN]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalization_errors() {
+        let m = array![[1., 0., -1.]];
+        assert!(normalize(m.view(), array![1.].view(), &Normalization::Max).is_err());
+        assert!(normalize(m.view(), array![1.].view(), &Normalization::Log).is_err());
+        // values below 1 have a negative sum of logarithms, which would flip the order
+        let m = array![[0.5, 0.25]];
+        assert!(normalize(m.view(), array![1.].view(), &Normalization::Log).is_err());
+        let m = array![[0.5, 2.]];
+        assert!(normalize(m.view(), array![1.].view(), &Normalization::Log).is_err());
+        let m = array![[0.5, 4.]];
+        let y = normalize(m.view(), array![1.].view(), &Normalization::Log).unwrap();
+        assert!(close(y.row(0).to_owned(), array![-1., 2.]));
+        assert!(normalize(m.view(), array![1., 1.].view(), &Normalization::MinMax).is_err());
+        assert!(normalize(m.view(), array![1.].view(), &Normalization::Target(vec![])).is_err());
+        assert!("minmax".parse::<Normalization>().is_ok());
+        assert!("l2".parse::<Normalization>().is_err());
+    }
+
+    #[test]
+    fn test_prom_normalize() -> Result<()> {
+        let mut p = Prom::new(
+            array![[10., 20., 40.], [0.8, 0.2, 0.4]],
+            Criteria::new(
+                array![1., 1.],
+                array![-1., 1.],
+                array!["linear".to_string(), "usual".to_string()],
+                array![5., 0.],
+                array![15., 0.],
+            )?,
+        )?;
+        let original = p.clone();
+        p.compute_prom_ii()?;
+        let before = p.prom_ii.clone().unwrap().score;
+
+        // a linear normalization with rescaled thresholds leaves the flows unchanged
+        p.normalize(&Normalization::MinMax)?;
+        assert_eq!(array![1., 1.], p.criteria.criteria_type);
+        assert!(close(p.criteria.q.clone(), array![1. / 6., 0.]));
+        assert!(p.prom_ii.is_none());
+        p.compute_prom_ii()?;
+        assert!(close(before, p.prom_ii.unwrap().score));
+
+        // the log of the first criterion cannot carry its thresholds along
+        assert!(original.clone().normalize(&Normalization::Log).is_err());
+        Ok(())
+    }
+}
@@ -41,6 +41,31 @@ pub fn outranking_adjacency_matrix(
     Ok(matrix)
 }
 
+pub fn outranking_edges(
+    phi_plus: ArrayView1<Fl>,
+    phi_minus: ArrayView1<Fl>,
+) -> Result<Vec<(usize, usize)>> {
+    let n = phi_plus.len();
+    let is_valid = n == phi_minus.len();
+
+    if !is_valid {
+        return Err(MCDMRSError::Error("Inputs must be of same length!".to_string()).into());
+    }
+
+    let mut edges: Vec<(usize, usize)> = Vec::new();
+    for i in 0..n {
+        let (ap, am) = (&phi_plus[i], &phi_minus[i]);
+        for j in 0..n {
+            let (bp, bm) = (&phi_plus[j], &phi_minus[j]);
+            if has_link_ab(ap, am, bp, bm) {
+                edges.push((i, j));
+            }
+        }
+    }
+
+    Ok(edges)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -95,5 +120,14 @@ mod tests {
         let exp: Array1<Fl> = Array1::from_iter(exp.iter()).mapv(|el| *el);
 
         assert_eq!(flat, exp);
+
+        let edges = outranking_edges(plus.view(), minus.view()).unwrap();
+        let from_adj: Vec<(usize, usize)> = adj
+            .indexed_iter()
+            .filter(|(_, v)| **v > 0.0)
+            .map(|(ij, _)| ij)
+            .collect();
+        assert_eq!(from_adj, edges);
+        assert!(outranking_edges(plus.view(), array![0.1].view()).is_err());
     }
 }
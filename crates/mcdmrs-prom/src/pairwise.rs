@@ -0,0 +1,295 @@
+use super::compute::{zip_for_each, ComputeOptions};
+use super::math::normalize_vec;
+use super::pref_functions::resolve;
+use super::promethee::{Criteria, Prom};
+use super::types::{Fl, MCDMRSError, Result};
+use ndarray::{Array1, Array2, ArrayView2, ArrayViewMut1, Axis, Zip};
+use std::ops::Range;
+
+/// The largest number of alternatives for which [`Prom::pi_matrix`] builds the n x n
+/// matrix without an explicit opt-in. At this size the matrix takes 64 MiB.
+pub const PAIRWISE_LIMIT: usize = 4096;
+
+fn check_range(range: &Range<usize>, n: usize) -> Result<()> {
+    if range.start > range.end || range.end > n {
+        return Err(MCDMRSError::Error(format!(
+            "Alternative range {:?} is out of bounds for {} alternatives",
+            range, n
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Returns the unweighted preference degree `P_j(a, b)` of alternative `a` over `b` on
+/// each criterion. Comparisons with a missing value are indifferent.
+pub fn preference_degrees(
+    matrix_t: ArrayView2<Fl>,
+    criteria: &Criteria,
+    a: usize,
+    b: usize,
+) -> Result<Array1<Fl>> {
+    let (m, n) = matrix_t.dim();
+    if a >= n || b >= n {
+        return Err(MCDMRSError::Error(format!(
+            "Alternative index out of bounds for {} alternatives",
+            n
+        ))
+        .into());
+    }
+
+    let funcs = criteria.preference_functions()?;
+    Ok(Array1::from_iter((0..m).map(|j| {
+        let diff = (matrix_t[[j, a]] - matrix_t[[j, b]]) * criteria.criteria_type[j];
+        if diff.is_nan() {
+            0.0
+        } else {
+            funcs[j].preference(&diff, &criteria.q[j], &criteria.p[j])
+        }
+    })))
+}
+
+/// Returns the unweighted preference degrees `P_j(a, b)` on criterion `j` for every
+/// `a` in `rows` and `b` in `cols`.
+pub fn preference_block(
+    matrix_t: ArrayView2<Fl>,
+    criteria: &Criteria,
+    j: usize,
+    rows: Range<usize>,
+    cols: Range<usize>,
+) -> Result<Array2<Fl>> {
+    let (m, n) = matrix_t.dim();
+    if j >= m {
+        return Err(MCDMRSError::Error(format!(
+            "Criterion index {} is out of bounds for {} criteria",
+            j, m
+        ))
+        .into());
+    }
+    check_range(&rows, n)?;
+    check_range(&cols, n)?;
+
+    let func = resolve(&criteria.pref_function[j], &criteria.functions)?;
+    let (t, q, p) = (criteria.criteria_type[j], criteria.q[j], criteria.p[j]);
+    let values = matrix_t.row(j);
+
+    Ok(Array2::from_shape_fn((rows.len(), cols.len()), |(a, b)| {
+        let diff = (values[rows.start + a] - values[cols.start + b]) * t;
+        if diff.is_nan() {
+            0.0
+        } else {
+            func.preference(&diff, &q, &p)
+        }
+    }))
+}
+
+/// Returns the aggregated preference index `pi(a, b) = sum_j w_j P_j(a, b)` for every
+/// pair of alternatives, with the weights normalized to sum to one.
+///
+/// This takes O(n^2) memory; see [`Prom::pi_matrix`] for a guarded, cached version.
+pub fn pi_matrix(
+    matrix_t: ArrayView2<Fl>,
+    criteria: &Criteria,
+    options: &ComputeOptions,
+) -> Result<Array2<Fl>> {
+    let (m, n) = matrix_t.dim();
+    let weight = normalize_vec(criteria.weight.view());
+    let funcs = criteria.preference_functions()?;
+    let mut pi = Array2::<Fl>::zeros((n, n));
+
+    zip_for_each!(
+        options,
+        Zip::indexed(pi.axis_iter_mut(Axis(0))),
+        |a: usize, mut row: ArrayViewMut1<Fl>| {
+            for j in 0..m {
+                let (t, q, p) = (criteria.criteria_type[j], criteria.q[j], criteria.p[j]);
+                let va = matrix_t[[j, a]];
+                if va.is_nan() {
+                    continue;
+                }
+                for (b, v) in row.iter_mut().enumerate() {
+                    let diff = (va - matrix_t[[j, b]]) * t;
+                    if !diff.is_nan() {
+                        *v += weight[j] * funcs[j].preference(&diff, &q, &p);
+                    }
+                }
+            }
+        }
+    );
+    Ok(pi)
+}
+
+impl Prom {
+    /// Returns the n x n aggregated preference index matrix, computing it on first use.
+    ///
+    /// Above [`PAIRWISE_LIMIT`] alternatives this returns an error unless `allow_large`
+    /// is set. The cached matrix is dropped when the flows are recomputed or re-weighted.
+    ///
+    /// The pairwise methods impute missing values under
+    /// [`MissingPolicy::Impute`](crate::missing::MissingPolicy::Impute) and do not support
+    /// the `Valid` and `Exclude` policies.
+    pub fn pi_matrix(&mut self, allow_large: bool) -> Result<&Array2<Fl>> {
+        let n = self.matrix_t.dim().1;
+        if n > PAIRWISE_LIMIT && !allow_large {
+            return Err(MCDMRSError::Error(format!(
+                "The pairwise matrix for {} alternatives needs {} MiB; pass `allow_large` to \
+                 compute it anyway",
+                n,
+                n * n * std::mem::size_of::<Fl>() / (1 << 20)
+            ))
+            .into());
+        }
+        if self.pi.is_none() {
+            let matrix_t = self.policy_matrix_t("The pairwise matrix")?;
+            let pi = pi_matrix(matrix_t.view(), &self.criteria, &self.options)?;
+            self.pi = Some(pi);
+        }
+        Ok(self.pi.as_ref().unwrap())
+    }
+
+    /// Returns the aggregated preference index `pi(a, b)` of one pair without building
+    /// the full matrix.
+    pub fn pi(&self, a: usize, b: usize) -> Result<Fl> {
+        let degrees = self.preference_degrees(a, b)?;
+        Ok(normalize_vec(self.criteria.weight.view()).dot(&degrees))
+    }
+
+    /// Returns the per-criterion preference degrees `P_j(a, b)` of one pair.
+    pub fn preference_degrees(&self, a: usize, b: usize) -> Result<Array1<Fl>> {
+        let matrix_t = self.policy_matrix_t("Pairwise preference degrees")?;
+        preference_degrees(matrix_t.view(), &self.criteria, a, b)
+    }
+
+    /// Returns the preference degrees `P_j(a, b)` on criterion `j` for a block of pairs.
+    pub fn preference_block(
+        &self,
+        j: usize,
+        rows: Range<usize>,
+        cols: Range<usize>,
+    ) -> Result<Array2<Fl>> {
+        let matrix_t = self.policy_matrix_t("Pairwise preference degrees")?;
+        preference_block(matrix_t.view(), &self.criteria, j, rows, cols)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::missing::{Impute, MissingPolicy};
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn test_preference_degrees() {
+        let mat = array![[0.8, 0.2, Fl::NAN], [0.1, 0.6, 0.4]];
+        let c = Criteria::new(
+            array![1., 1.],
+            array![-1., 1.],
+            array!["usual".to_string(), "linear".to_string()],
+            array![0., 0.],
+            array![0., 1.],
+        )
+        .unwrap();
+
+        assert_eq!(
+            array![0., 0.],
+            preference_degrees(mat.view(), &c, 0, 1).unwrap()
+        );
+        let p = preference_degrees(mat.view(), &c, 1, 0).unwrap();
+        assert_eq!(1.0, p[0]);
+        assert!((p[1] - 0.5).abs() < 1e-6);
+        assert_eq!(0.0, preference_degrees(mat.view(), &c, 2, 0).unwrap()[0]);
+        assert!(preference_degrees(mat.view(), &c, 3, 0).is_err());
+    }
+
+    #[test]
+    fn test_pi_matrix() -> Result<()> {
+        let mut p = Prom::new(
+            array![[0.8, 0.2, 0.05, Fl::NAN], [0.1, 0.6, 0.4, 0.3]],
+            Criteria::new(
+                array![1., 3.],
+                array![-1., 1.],
+                array!["usual".to_string(), "vshape".to_string()],
+                array![0., 0.],
+                array![0., 0.5],
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        p.compute_prom_ii()?;
+        let phi_plus = p.prom_i.as_ref().unwrap().phi_plus_score.clone();
+        let phi_minus = p.prom_i.as_ref().unwrap().phi_minus_score.clone();
+
+        let pi = p.pi_matrix(false)?.clone();
+        assert_eq!((4, 4), pi.dim());
+        assert_eq!(array![0., 0., 0., 0.], pi.diag());
+        assert!(pi.iter().all(|v| (0.0..=1.0).contains(v)));
+        assert!((pi[[1, 0]] - p.pi(1, 0)?).abs() < 1e-6);
+
+        // the row and column means are the positive and negative flows
+        let n1 = 3.0;
+        for i in 0..4 {
+            assert!((pi.row(i).sum() / n1 - phi_plus[i]).abs() < 1e-6);
+            assert!((pi.column(i).sum() / n1 - phi_minus[i]).abs() < 1e-6);
+        }
+
+        let block = p.preference_block(1, 0..2, 1..4)?;
+        assert_eq!((2, 3), block.dim());
+        assert_eq!(p.preference_degrees(1, 2)?[1], block[[1, 1]]);
+        assert!(p.preference_block(2, 0..2, 0..2).is_err());
+        assert!(p.preference_block(0, 0..5, 0..2).is_err());
+
+        p.re_weight(array![1., 1.].view())?;
+        assert!(p.pi.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_policy() -> Result<()> {
+        let criteria = Criteria::new(
+            array![1., 1.],
+            array![-1., 1.],
+            array!["usual".to_string(), "usual".to_string()],
+            array![0., 0.],
+            array![0., 0.],
+        )?;
+        let mut p = Prom::new(
+            array![[0.8, Fl::NAN, 0.2], [0.1, 0.6, 0.4]],
+            criteria.clone(),
+        )?
+        .with_missing_policy(MissingPolicy::Impute(Impute::Best));
+        let mut filled = Prom::new(array![[0.8, 0.2, 0.2], [0.1, 0.6, 0.4]], criteria)?;
+        assert_eq!(filled.pi_matrix(false)?, p.pi_matrix(false)?);
+        assert_eq!(filled.pi(1, 0)?, p.pi(1, 0)?);
+        assert_eq!(
+            filled.preference_block(0, 0..3, 0..3)?,
+            p.preference_block(0, 0..3, 0..3)?
+        );
+
+        for missing in [MissingPolicy::Valid, MissingPolicy::Exclude] {
+            let mut p = p.clone().with_missing_policy(missing);
+            assert!(p.pi_matrix(false).is_err());
+            assert!(p.pi(1, 0).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_pi_matrix_guard() {
+        let n = PAIRWISE_LIMIT + 1;
+        let mut p = Prom::new(
+            Array2::zeros((1, n)),
+            Criteria::new(
+                array![1.],
+                array![1.],
+                array!["usual".to_string()],
+                array![0.],
+                array![0.],
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(p.pi_matrix(false).is_err());
+        assert!(p.pi.is_none());
+    }
+}
@@ -0,0 +1,325 @@
+use super::pref_functions::PrefFunctions;
+use super::promethee::Prom;
+use super::types::{MCDMRSError, Result};
+use core::fmt;
+use serde::de::{self, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+
+/// The current version of the saved analysis wire format. Bump this whenever a
+/// change to a serialized type would make previously saved analyses unreadable.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// A versioned envelope for persisting a `Prom` analysis.
+///
+/// The cached preference matrices are saved along with the inputs, so a reloaded
+/// analysis can be re-weighted without recomputing them. The version is written
+/// first and checked before the analysis is parsed.
+///
+/// JSON has no representation for NaN, which serde_json writes as `null` and cannot
+/// read back. Save analyses with missing values or `Exclude` results in a format
+/// that keeps non-finite floats, e.g., bincode.
+#[derive(Clone, Debug, Serialize)]
+pub struct SavedProm {
+    pub version: u32,
+    pub prom: Prom,
+}
+
+fn check_version(version: u32) -> Result<()> {
+    if version != FORMAT_VERSION {
+        return Err(MCDMRSError::Error(format!(
+            "Unsupported saved analysis version {}, expected {}",
+            version, FORMAT_VERSION
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+const FIELDS: &[&str] = &["version", "prom"];
+
+struct SavedPromVisitor;
+
+impl<'de> Visitor<'de> for SavedPromVisitor {
+    type Value = SavedProm;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a saved analysis")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> core::result::Result<SavedProm, A::Error> {
+        let version: u32 = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        check_version(version).map_err(de::Error::custom)?;
+        let prom = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        Ok(SavedProm { version, prom })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> core::result::Result<SavedProm, A::Error> {
+        let mut version = None;
+        let mut prom = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "version" => {
+                    let v = map.next_value()?;
+                    check_version(v).map_err(de::Error::custom)?;
+                    version = Some(v);
+                }
+                "prom" => prom = Some(map.next_value()?),
+                _ => {
+                    map.next_value::<de::IgnoredAny>()?;
+                }
+            }
+        }
+        let version = version.ok_or_else(|| de::Error::missing_field("version"))?;
+        let prom = prom.ok_or_else(|| de::Error::missing_field("prom"))?;
+        Ok(SavedProm { version, prom })
+    }
+}
+
+impl<'de> Deserialize<'de> for SavedProm {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> core::result::Result<Self, D::Error> {
+        deserializer.deserialize_struct("SavedProm", FIELDS, SavedPromVisitor)
+    }
+}
+
+impl SavedProm {
+    pub fn new(prom: Prom) -> SavedProm {
+        SavedProm {
+            version: FORMAT_VERSION,
+            prom,
+        }
+    }
+
+    /// Returns the saved analysis after running the checks of the constructors on it,
+    /// since deserializing bypasses them. Custom preference functions are not saved,
+    /// so an analysis that uses them must be loaded with [`SavedProm::into_prom_with`].
+    pub fn into_prom(self) -> Result<Prom> {
+        self.into_prom_with(PrefFunctions::new())
+    }
+
+    /// Like [`SavedProm::into_prom`], registering the custom preference functions the
+    /// analysis refers to.
+    pub fn into_prom_with(self, functions: PrefFunctions) -> Result<Prom> {
+        check_version(self.version)?;
+        let mut saved = self.prom;
+        saved.criteria.functions = functions;
+        if let Err(e) = saved.criteria.preference_functions() {
+            return Err(MCDMRSError::Error(format!(
+                "{}, custom preference functions must be registered when loading",
+                e
+            ))
+            .into());
+        }
+        let constraints = saved.criteria.weight_constraints.clone();
+        let criteria = saved.criteria.with_weight_constraints(constraints)?;
+        let mut prom = Prom::new(saved.matrix_t, criteria)?.with_missing_policy(saved.missing);
+        if let Some(labels) = saved.labels {
+            prom = prom.with_labels(labels)?;
+        }
+
+        let (m, n) = prom.matrix_t.dim();
+        let is_valid = saved.mc_flow.as_ref().is_none_or(|mc| {
+            mc.pref_matrix_plus_t.dim() == (m, n) && mc.pref_matrix_minus_t.dim() == (m, n)
+        }) && saved.prom_i.as_ref().is_none_or(|pi| {
+            pi.phi_plus_score.len() == n
+                && pi.phi_minus_score.len() == n
+                && pi.phi_plus_matrix.dim() == (n, m)
+                && pi.phi_minus_matrix.dim() == (n, m)
+        }) && saved.prom_ii.as_ref().is_none_or(|pii| {
+            pii.score.len() == n
+                && pii.normalized_score.len() == n
+                && pii.weighted_flow.dim() == (n, m)
+        });
+        if !is_valid {
+            return Err(MCDMRSError::Error(
+                "The saved results do not match the dimensions of `matrix_t`".to_string(),
+            )
+            .into());
+        }
+        prom.mc_flow = saved.mc_flow;
+        prom.prom_i = saved.prom_i;
+        prom.prom_ii = saved.prom_ii;
+        Ok(prom)
+    }
+}
+
+impl Prom {
+    pub fn to_saved(&self) -> SavedProm {
+        SavedProm::new(self.clone())
+    }
+
+    pub fn from_saved(saved: SavedProm) -> Result<Prom> {
+        saved.into_prom()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::missing::MissingPolicy;
+    use super::super::types::Fl;
+    use super::super::utils::get_prom;
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let mut p = get_prom();
+        p.compute_prom_ii()?;
+
+        let json = serde_json::to_string(&p.to_saved())?;
+        let mut loaded = Prom::from_saved(serde_json::from_str(&json)?)?;
+
+        assert_eq!(p.matrix_t, loaded.matrix_t);
+        assert_eq!(p.criteria.pref_function, loaded.criteria.pref_function);
+        assert_eq!(
+            p.mc_flow.as_ref().unwrap().pref_matrix_plus_t,
+            loaded.mc_flow.as_ref().unwrap().pref_matrix_plus_t
+        );
+        assert_eq!(
+            p.prom_ii.as_ref().unwrap().score,
+            loaded.prom_ii.as_ref().unwrap().score
+        );
+
+        // re-weighting the reloaded analysis reuses the saved preference matrices
+        let weight = array![0.2, 0.8];
+        p.re_weight(weight.view())?;
+        loaded.mc_flow.as_mut().unwrap().pref_matrix_minus_t[[0, 0]] += 1.0;
+        loaded.re_weight(weight.view())?;
+        assert_ne!(
+            p.prom_ii.as_ref().unwrap().score,
+            loaded.prom_ii.as_ref().unwrap().score
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_version_mismatch() -> Result<()> {
+        let mut saved = get_prom().to_saved();
+        saved.version = FORMAT_VERSION + 1;
+
+        let json = serde_json::to_string(&saved)?;
+        let err = serde_json::from_str::<SavedProm>(&json).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unsupported saved analysis version"));
+
+        // the version is checked before the payload is parsed
+        let json = format!(
+            r#"{{"version": {}, "prom": {{"a": 1}}}}"#,
+            FORMAT_VERSION + 1
+        );
+        let err = serde_json::from_str::<SavedProm>(&json).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unsupported saved analysis version"));
+
+        let bytes = bincode::serialize(&saved)?;
+        let err = bincode::deserialize::<SavedProm>(&bytes).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unsupported saved analysis version"));
+
+        assert!(Prom::from_saved(saved).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_finite_round_trip() -> Result<()> {
+        let mut p = get_prom();
+        p.matrix_t[[1, 2]] = Fl::NAN;
+        p.compute_prom_ii()?;
+
+        let bytes = bincode::serialize(&p.to_saved())?;
+        let loaded = Prom::from_saved(bincode::deserialize(&bytes)?)?;
+        assert!(loaded.matrix_t[[1, 2]].is_nan());
+        assert_eq!(
+            p.prom_ii.as_ref().unwrap().score,
+            loaded.prom_ii.as_ref().unwrap().score
+        );
+
+        // JSON writes NaN as null, which does not read back as a float
+        let json = serde_json::to_string(&p.to_saved())?;
+        assert!(serde_json::from_str::<SavedProm>(&json).is_err());
+
+        // the alternatives excluded from the ranking have NaN results
+        let mut p = get_prom().with_missing_policy(MissingPolicy::Exclude);
+        p.matrix_t[[0, 1]] = Fl::NAN;
+        p.compute_prom_ii()?;
+        let bytes = bincode::serialize(&p.to_saved())?;
+        let loaded = Prom::from_saved(bincode::deserialize(&bytes)?)?;
+        assert_eq!(MissingPolicy::Exclude, loaded.missing);
+        let (score, loaded_score) = (
+            &p.prom_ii.as_ref().unwrap().score,
+            &loaded.prom_ii.as_ref().unwrap().score,
+        );
+        assert!(loaded_score[1].is_nan());
+        assert!(score
+            .iter()
+            .zip(loaded_score)
+            .all(|(a, b)| a == b || (a.is_nan() && b.is_nan())));
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_function() -> Result<()> {
+        use super::super::pref_functions::{PiecewiseLinear, PreferenceFunction};
+        use std::sync::Arc;
+
+        let survey = PiecewiseLinear::new(vec![(0.1, 0.0), (0.2, 0.8), (0.4, 1.0)])?;
+        let mut p = get_prom();
+        p.criteria = p.criteria.with_function("survey", survey.clone())?;
+        p.criteria.pref_function[1] = "survey".to_string();
+        p.compute_prom_ii()?;
+        let json = serde_json::to_string(&p.to_saved())?;
+
+        // the registry is not saved, so the function has to be registered again
+        assert!(Prom::from_saved(serde_json::from_str(&json)?).is_err());
+        let mut functions = PrefFunctions::new();
+        functions.insert(
+            "survey".to_string(),
+            Arc::new(survey) as Arc<dyn PreferenceFunction>,
+        );
+        let saved: SavedProm = serde_json::from_str(&json)?;
+        let mut loaded = saved.into_prom_with(functions)?;
+        loaded.compute_multicriterion_flow()?;
+        loaded.compute_prom_ii()?;
+        assert_eq!(
+            p.prom_ii.as_ref().unwrap().score,
+            loaded.prom_ii.as_ref().unwrap().score
+        );
+
+        // recomputing a deserialized analysis without the registry is an error
+        let saved: SavedProm = serde_json::from_str(&json)?;
+        let mut p = saved.prom;
+        assert!(p.compute_multicriterion_flow().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validation() -> Result<()> {
+        let mut p =
+            get_prom().with_labels(array!["a".to_string(), "b".to_string(), "c".to_string()])?;
+        p.compute_prom_ii()?;
+        let json = serde_json::to_string(&p.to_saved())?;
+
+        let mut value: serde_json::Value = serde_json::from_str(&json)?;
+        value["prom"]["labels"]["data"] = serde_json::json!(["a", "b"]);
+        value["prom"]["labels"]["dim"] = serde_json::json!([2]);
+        assert!(Prom::from_saved(serde_json::from_value(value)?).is_err());
+
+        let mut value: serde_json::Value = serde_json::from_str(&json)?;
+        value["prom"]["criteria"]["q"]["data"] = serde_json::json!([0.0]);
+        value["prom"]["criteria"]["q"]["dim"] = serde_json::json!([1]);
+        assert!(Prom::from_saved(serde_json::from_value(value)?).is_err());
+
+        let mut value: serde_json::Value = serde_json::from_str(&json)?;
+        value["prom"]["prom_ii"]["score"]["data"] = serde_json::json!([0.0]);
+        value["prom"]["prom_ii"]["score"]["dim"] = serde_json::json!([1]);
+        assert!(Prom::from_saved(serde_json::from_value(value)?).is_err());
+        Ok(())
+    }
+}
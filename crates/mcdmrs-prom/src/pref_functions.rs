@@ -1,5 +1,11 @@
 use super::cmp::{gt, le, lt};
-use super::types::{FPref, Fl};
+use super::types::{FPref, Fl, MCDMRSError, Result};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::Debug;
 
 pub fn usual(d: &Fl, _q: &Fl, _p: &Fl) -> Fl {
     if gt(d, &0.0) {
@@ -47,17 +53,122 @@ pub fn level(d: &Fl, q: &Fl, p: &Fl) -> Fl {
     }
 }
 
+pub const PREF_FUNCTIONS: [&str; 7] = [
+    "usual", "ushape", "vshape", "vshape2", "vshape_2", "linear", "level",
+];
+
+pub fn is_pref_function(name: &str) -> bool {
+    PREF_FUNCTIONS.contains(&name)
+}
+
 pub fn _get_pref_function(name: &str) -> FPref {
     match name {
         "usual" => usual,
         "ushape" => ushape,
         "vshape" => vshape,
-        "vshape2" => vshape2,
+        "vshape2" | "vshape_2" | "linear" => vshape2,
         "level" => level,
         _ => usual,
     }
 }
 
+/// Maps the difference `d` between two alternatives to a preference degree in `[0, 1]`.
+pub trait PreferenceFunction: Debug + Send + Sync {
+    fn preference(&self, d: &Fl, q: &Fl, p: &Fl) -> Fl;
+}
+
+/// The custom preference functions of [`Criteria`](super::Criteria), by name.
+pub type PrefFunctions = BTreeMap<String, Arc<dyn PreferenceFunction>>;
+
+macro_rules! builtin_pref_function {
+    ($struct_name:ident, $func:ident) => {
+        #[derive(Clone, Copy, Debug, Default)]
+        pub struct $struct_name;
+
+        impl PreferenceFunction for $struct_name {
+            fn preference(&self, d: &Fl, q: &Fl, p: &Fl) -> Fl {
+                $func(d, q, p)
+            }
+        }
+    };
+}
+
+builtin_pref_function!(Usual, usual);
+builtin_pref_function!(UShape, ushape);
+builtin_pref_function!(VShape, vshape);
+builtin_pref_function!(VShape2, vshape2);
+builtin_pref_function!(Level, level);
+
+/// Returns the built-in preference function with this name.
+pub fn builtin(name: &str) -> Option<Arc<dyn PreferenceFunction>> {
+    match name {
+        "usual" => Some(Arc::new(Usual)),
+        "ushape" => Some(Arc::new(UShape)),
+        "vshape" => Some(Arc::new(VShape)),
+        "vshape2" | "vshape_2" | "linear" => Some(Arc::new(VShape2)),
+        "level" => Some(Arc::new(Level)),
+        _ => None,
+    }
+}
+
+/// Returns the built-in preference function with this name, or else the custom one.
+pub fn resolve(name: &str, functions: &PrefFunctions) -> Result<Arc<dyn PreferenceFunction>> {
+    match builtin(name).or_else(|| functions.get(name).cloned()) {
+        Some(f) => Ok(f),
+        None => Err(MCDMRSError::Error(format!("invalid preference function: {:?}", name)).into()),
+    }
+}
+
+/// Interpolates linearly between `(d, P(d))` breakpoints, starting at `(0, 0)`. The
+/// `q` and `p` thresholds are unused.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PiecewiseLinear {
+    breakpoints: Vec<(Fl, Fl)>,
+}
+
+impl PiecewiseLinear {
+    /// The differences must increase and the degrees must not decrease.
+    pub fn new(breakpoints: Vec<(Fl, Fl)>) -> Result<PiecewiseLinear> {
+        let in_range = breakpoints
+            .iter()
+            .all(|(d, v)| *d > 0.0 && (0.0..=1.0).contains(v));
+        let increasing = breakpoints
+            .windows(2)
+            .all(|w| w[0].0 < w[1].0 && w[0].1 <= w[1].1);
+
+        if breakpoints.is_empty() || !in_range || !increasing {
+            return Err(MCDMRSError::Error(
+                "Breakpoints must have increasing positive differences and non-decreasing \
+                 preference degrees between 0 and 1"
+                    .to_string(),
+            )
+            .into());
+        }
+        Ok(PiecewiseLinear { breakpoints })
+    }
+
+    pub fn breakpoints(&self) -> &[(Fl, Fl)] {
+        &self.breakpoints
+    }
+}
+
+impl PreferenceFunction for PiecewiseLinear {
+    fn preference(&self, d: &Fl, _q: &Fl, _p: &Fl) -> Fl {
+        if !gt(d, &0.0) {
+            return 0.0;
+        }
+        let (mut d0, mut v0) = (0.0, 0.0);
+        for &(d1, v1) in self.breakpoints.iter() {
+            if le(d, &d1) {
+                return v0 + (v1 - v0) * (d - d0) / (d1 - d0);
+            }
+            (d0, v0) = (d1, v1);
+        }
+        v0
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,6 +182,47 @@ mod tests {
         assert_eq!(_get_pref_function("level")(&0.0, &0.0, &0.0), 0.0);
     }
 
+    #[test]
+    fn test_builtin_trait() {
+        for name in PREF_FUNCTIONS {
+            let f = builtin(name).unwrap();
+            let g = _get_pref_function(name);
+            for d in [-1.0, 0.0, 0.2, 0.5, 0.9, 2.0] {
+                assert_eq!(g(&d, &0.1, &1.0), f.preference(&d, &0.1, &1.0));
+            }
+        }
+        assert!(builtin("gaussian").is_none());
+
+        let mut functions = PrefFunctions::new();
+        assert!(resolve("survey", &functions).is_err());
+        functions.insert("survey".to_string(), Arc::new(Usual));
+        assert!(resolve("survey", &functions).is_ok());
+    }
+
+    #[test]
+    fn test_piecewise_linear() {
+        let f = PiecewiseLinear::new(vec![(1.0, 0.2), (2.0, 0.8), (4.0, 1.0)]).unwrap();
+        assert_eq!(0.0, f.preference(&-1.0, &0.0, &0.0));
+        assert_eq!(0.0, f.preference(&0.0, &0.0, &0.0));
+        assert_eq!(0.1, f.preference(&0.5, &0.0, &0.0));
+        assert_eq!(0.5, f.preference(&1.5, &0.0, &0.0));
+        assert_eq!(0.9, f.preference(&3.0, &0.0, &0.0));
+        assert_eq!(1.0, f.preference(&10.0, &0.0, &0.0));
+
+        assert!(PiecewiseLinear::new(vec![]).is_err());
+        assert!(PiecewiseLinear::new(vec![(2.0, 0.2), (1.0, 0.8)]).is_err());
+        assert!(PiecewiseLinear::new(vec![(1.0, 0.8), (2.0, 0.2)]).is_err());
+        assert!(PiecewiseLinear::new(vec![(0.0, 0.5)]).is_err());
+        assert!(PiecewiseLinear::new(vec![(1.0, 1.5)]).is_err());
+    }
+
+    #[test]
+    fn test_is_pref_function() {
+        assert!(is_pref_function("linear"));
+        assert!(is_pref_function("vshape_2"));
+        assert!(!is_pref_function("gaussian"));
+    }
+
     macro_rules! parametrize_pref_functions {
         ($($name:ident: $value:expr,)*) => {
         $(
@@ -1,15 +1,41 @@
-use super::math::{min_max_norm, mult_axis_0, normalize_vec};
+use super::compute::ComputeOptions;
+use super::hierarchy::Hierarchy;
+use super::imprecise::WeightConstraint;
+use super::math::{min_max_norm, mult_axis_0, normalize_vec, rank};
+use super::missing::{multicriterion_flow_with_policy, MissingPolicy};
 use super::multicriterion_flow::MCFlowResult;
+use super::pref_functions::{builtin, resolve, PrefFunctions, PreferenceFunction};
+use super::scale::OrdinalScale;
 use super::types::{Fl, MCDMRSError, Result};
 use ndarray::{Array1, Array2, ArrayView1, ArrayView2, Axis};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Criteria {
     pub weight: Array1<Fl>,
     pub criteria_type: Array1<Fl>,
     pub pref_function: Array1<String>,
     pub q: Array1<Fl>,
     pub p: Array1<Fl>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub name: Option<Array1<String>>,
+    /// The ordinal scale of each qualitative criterion, `None` for numeric criteria.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub scale: Option<Array1<Option<OrdinalScale>>>,
+    /// Custom preference functions, referenced by name from `pref_function`. They are
+    /// not serialized, so a deserialized `Criteria` must register them again with
+    /// [`Criteria::with_function`] before computing any flows.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub functions: PrefFunctions,
+    /// The criteria hierarchy the weights were propagated from, see
+    /// [`Criteria::with_hierarchy`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub hierarchy: Option<Hierarchy>,
+    /// Imprecise weight information, see [`Criteria::with_weight_constraints`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub weight_constraints: Vec<WeightConstraint>,
 }
 
 impl Criteria {
@@ -33,14 +59,67 @@ impl Criteria {
                 pref_function,
                 q,
                 p,
+                name: None,
+                scale: None,
+                functions: PrefFunctions::new(),
+                hierarchy: None,
+                weight_constraints: vec![],
             })
         } else {
             Err(MCDMRSError::Error("All members must be of same length!".to_string()).into())
         }
     }
+
+    /// Attaches a name to each criterion so results can be queried by name.
+    pub fn with_names(mut self, name: Array1<String>) -> Result<Criteria> {
+        if name.len() != self.weight.len() {
+            return Err(
+                MCDMRSError::Error("All members must be of same length!".to_string()).into(),
+            );
+        }
+        self.name = Some(name);
+        Ok(self)
+    }
+
+    /// Registers a custom preference function that criteria can use by setting their
+    /// `pref_function` to `name`. Built-in names cannot be overridden.
+    pub fn with_function<F>(mut self, name: &str, function: F) -> Result<Criteria>
+    where
+        F: PreferenceFunction + 'static,
+    {
+        if builtin(name).is_some() {
+            return Err(MCDMRSError::Error(format!(
+                "`{}` is a built-in preference function",
+                name
+            ))
+            .into());
+        }
+        self.functions.insert(name.to_string(), Arc::new(function));
+        Ok(self)
+    }
+
+    /// Returns the preference function of each criterion.
+    pub fn preference_functions(&self) -> Result<Vec<Arc<dyn PreferenceFunction>>> {
+        self.pref_function
+            .iter()
+            .map(|name| resolve(name, &self.functions))
+            .collect()
+    }
+
+    /// Declares an ordinal scale for the qualitative criteria.
+    pub fn with_scales(mut self, scale: Array1<Option<OrdinalScale>>) -> Result<Criteria> {
+        if scale.len() != self.weight.len() {
+            return Err(
+                MCDMRSError::Error("All members must be of same length!".to_string()).into(),
+            );
+        }
+        self.scale = Some(scale);
+        Ok(self)
+    }
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PromResultI {
     pub phi_plus_score: Array1<Fl>,
     pub phi_minus_score: Array1<Fl>,
@@ -75,10 +154,13 @@ impl PromResultI {
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PromResultII {
     pub score: Array1<Fl>,
     pub normalized_score: Array1<Fl>,
     pub weighted_flow: Array2<Fl>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rank: OnceLock<Array1<usize>>,
 }
 
 pub fn prom_ii(p: &PromResultI) -> Result<PromResultII> {
@@ -90,6 +172,7 @@ pub fn prom_ii(p: &PromResultI) -> Result<PromResultII> {
         score,
         normalized_score,
         weighted_flow,
+        rank: OnceLock::new(),
     })
 }
 
@@ -97,20 +180,44 @@ impl PromResultII {
     pub fn new(p: &PromResultI) -> Result<Self> {
         prom_ii(p)
     }
+
+    /// Returns the 1-based rank of each alternative, see [`rank`]. The ranks are
+    /// computed on the first call.
+    pub fn ranks(&self) -> &Array1<usize> {
+        self.rank.get_or_init(|| rank(self.score.view()))
+    }
 }
 
 #[derive(Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Prom {
     pub matrix_t: Array2<Fl>,
     pub criteria: Criteria,
     pub mc_flow: Option<MCFlowResult>,
     pub prom_i: Option<PromResultI>,
     pub prom_ii: Option<PromResultII>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub labels: Option<Array1<String>>,
+    /// Maps each label to its alternative, built by [`Prom::with_labels`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub label_index: Option<HashMap<String, usize>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub missing: MissingPolicy,
+    /// The cached aggregated preference index matrix, see [`Prom::pi_matrix`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub pi: Option<Array2<Fl>>,
+    /// Where the parallel kernels run.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub options: ComputeOptions,
 }
 
+/// Sets the criteria weights and recomputes Promethee II. The weights no longer follow
+/// the criteria hierarchy, which is dropped.
 pub fn re_weight(p: &mut Prom, weight: ArrayView1<Fl>) -> Result<()> {
     p.criteria.weight = weight.to_owned();
+    p.criteria.hierarchy = None;
     p.prom_i = None;
+    p.pi = None;
     p.compute_prom_ii()?;
 
     Ok(())
@@ -143,7 +250,9 @@ impl Prom {
             && m == criteria.criteria_type.len()
             && m == criteria.pref_function.len()
             && m == criteria.q.len()
-            && m == criteria.p.len();
+            && m == criteria.p.len()
+            && criteria.name.as_ref().is_none_or(|name| m == name.len())
+            && criteria.scale.as_ref().is_none_or(|scale| m == scale.len());
 
         if is_valid {
             Ok(Prom {
@@ -152,6 +261,11 @@ impl Prom {
                 mc_flow: None,
                 prom_i: None,
                 prom_ii: None,
+                labels: None,
+                label_index: None,
+                missing: MissingPolicy::default(),
+                pi: None,
+                options: ComputeOptions::default(),
             })
         } else {
             Err(MCDMRSError::Error(
@@ -162,14 +276,49 @@ impl Prom {
         }
     }
 
+    /// Attaches a label to each alternative so results can be queried by label. Labels
+    /// must be unique and non-empty.
+    pub fn with_labels(mut self, labels: Array1<String>) -> Result<Prom> {
+        if labels.len() != self.matrix_t.dim().1 {
+            return Err(MCDMRSError::Error(
+                "The 1 dimension of `matrix_t` must be of same length as `labels`".to_string(),
+            )
+            .into());
+        }
+        if let Some(i) = labels.iter().position(|l| l.is_empty()) {
+            return Err(
+                MCDMRSError::Error(format!("The label of alternative {} is empty", i)).into(),
+            );
+        }
+        let mut index = HashMap::with_capacity(labels.len());
+        for (i, label) in labels.iter().enumerate() {
+            if index.insert(label.clone(), i).is_some() {
+                return Err(MCDMRSError::Error(format!("Duplicate label `{}`", label)).into());
+            }
+        }
+        self.labels = Some(labels);
+        self.label_index = Some(index);
+        Ok(self)
+    }
+
+    /// Sets where the parallel kernels run, see [`ComputeOptions`].
+    pub fn with_options(mut self, options: ComputeOptions) -> Prom {
+        self.options = options;
+        self
+    }
+
     pub fn compute_multicriterion_flow(&mut self) -> Result<()> {
         let mat = mult_axis_0(self.matrix_t.view(), self.criteria.criteria_type.view())?;
+        self.pi = None;
 
-        self.mc_flow = Some(MCFlowResult::new(
+        self.mc_flow = Some(multicriterion_flow_with_policy(
             mat.view(),
             self.criteria.pref_function.view(),
             self.criteria.q.view(),
             self.criteria.p.view(),
+            &self.criteria.functions,
+            self.missing,
+            &self.options,
         )?);
 
         Ok(())
@@ -235,10 +384,20 @@ mod test {
                 pref_function: array!["usual".to_string(), "usual".to_string()],
                 q: array![0., 0.],
                 p: array![0., 0.],
+                name: None,
+                scale: None,
+                functions: PrefFunctions::new(),
+                hierarchy: None,
+                weight_constraints: vec![],
             },
             mc_flow: None,
             prom_i: None,
             prom_ii: None,
+            labels: None,
+            label_index: None,
+            missing: MissingPolicy::Skip,
+            pi: None,
+            options: Default::default(),
         };
 
         let mut p: Prom = Prom::new(
@@ -249,6 +408,11 @@ mod test {
                 pref_function: array!["usual".to_string(), "usual".to_string()],
                 q: array![0., 0.],
                 p: array![0., 0.],
+                name: None,
+                scale: None,
+                functions: PrefFunctions::new(),
+                hierarchy: None,
+                weight_constraints: vec![],
             },
         )
         .unwrap();
@@ -362,6 +526,11 @@ mod test {
                 pref_function,
                 q: Array1::<Fl>::from_vec(newq),
                 p,
+                name: None,
+                scale: None,
+                functions: PrefFunctions::new(),
+                hierarchy: None,
+                weight_constraints: vec![],
             },
         );
 
@@ -524,6 +693,50 @@ mod test {
         assert!(all_close!(exp_promii, score, abs_tol = 1e-3))
     }
 
+    #[test]
+    fn test_complex_prom_custom() {
+        use super::super::pref_functions::VShape2;
+        use is_close::all_close;
+
+        #[derive(Debug)]
+        struct Survey;
+
+        impl PreferenceFunction for Survey {
+            fn preference(&self, d: &Fl, q: &Fl, p: &Fl) -> Fl {
+                VShape2.preference(d, q, p)
+            }
+        }
+
+        let (matrix, weights, criteria_types, _prefs, q, p) = get_prom_inputs();
+        let builtin_prefs = Array1::from(vec!["vshape2".to_string(); weights.len()]);
+        let custom_prefs = Array1::from(vec!["survey".to_string(); weights.len()]);
+
+        let c = Criteria::new(
+            weights.clone(),
+            criteria_types.clone(),
+            builtin_prefs,
+            q.clone(),
+            p.clone(),
+        )
+        .unwrap();
+        let mut expected = Prom::new(matrix.clone(), c).unwrap();
+        expected.compute_prom_ii().unwrap();
+
+        let c = Criteria::new(weights, criteria_types, custom_prefs, q, p)
+            .unwrap()
+            .with_function("survey", Survey)
+            .unwrap();
+        assert!(c.clone().with_function("usual", Survey).is_err());
+        let mut p = Prom::new(matrix, c).unwrap();
+        p.compute_prom_ii().unwrap();
+
+        assert!(all_close!(
+            expected.prom_ii.unwrap().score,
+            p.prom_ii.unwrap().score,
+            abs_tol = 1e-6
+        ))
+    }
+
     #[test]
     fn test_complex_prom_level() {
         use is_close::all_close;
@@ -0,0 +1,313 @@
+//! Rank reversal diagnostics for removed or duplicated alternatives.
+use super::cmp::{gt, lt};
+use super::compute::zip_for_each;
+use super::math::{mult_axis_0, normalize_vec};
+use super::missing::{impute, MissingPolicy};
+use super::promethee::Prom;
+use super::types::{Fl, MCDMRSError, Result};
+use ndarray::{Array1, Array2, ArrayView1, Axis, Zip};
+
+/// A change to the set of alternatives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Scenario {
+    /// The alternative is left out.
+    Remove(usize),
+    /// An exact copy of the alternative is added.
+    Copy(usize),
+}
+
+impl Scenario {
+    fn alternative(&self) -> usize {
+        match *self {
+            Scenario::Remove(k) | Scenario::Copy(k) => k,
+        }
+    }
+}
+
+/// A pair of alternatives where `a` was ahead of `b` and falls behind it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Swap {
+    pub a: usize,
+    pub b: usize,
+    /// The net flow of `a` minus that of `b` before the change, positive.
+    pub before: Fl,
+    /// ... and in the scenario, negative.
+    pub after: Fl,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScenarioReport {
+    pub scenario: Scenario,
+    /// The net flows of the original alternatives, NaN for a removed one.
+    pub score: Array1<Fl>,
+    pub swaps: Vec<Swap>,
+}
+
+/// The result of [`Prom::rank_reversal`].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RankReversal {
+    /// The net flows of the original alternatives.
+    pub score: Array1<Fl>,
+    pub scenarios: Vec<ScenarioReport>,
+}
+
+impl RankReversal {
+    /// Returns true when some scenario flips the order of a pair.
+    pub fn has_reversal(&self) -> bool {
+        self.scenarios.iter().any(|s| !s.swaps.is_empty())
+    }
+
+    /// Returns the scenarios that flip the order of at least one pair.
+    pub fn reversals(&self) -> impl Iterator<Item = &ScenarioReport> {
+        self.scenarios.iter().filter(|s| !s.swaps.is_empty())
+    }
+}
+
+/// Lists the pairs that are strictly ordered in `before` and reversed in `after`.
+fn swaps(before: ArrayView1<Fl>, after: ArrayView1<Fl>) -> Vec<Swap> {
+    let mut order: Vec<usize> = (0..before.len())
+        .filter(|&i| !before[i].is_nan() && !after[i].is_nan())
+        .collect();
+    order.sort_by(|&a, &b| before[b].total_cmp(&before[a]));
+
+    let mut found = vec![];
+    merge_sort(&mut order, after, &mut |l, r| {
+        if gt(&before[l], &before[r]) && lt(&after[l], &after[r]) {
+            found.push(Swap {
+                a: l,
+                b: r,
+                before: before[l] - before[r],
+                after: after[l] - after[r],
+            });
+        }
+    });
+    found.sort_by_key(|s| (s.a, s.b));
+    found
+}
+
+/// Stably sorts `v` by decreasing `key`, calling `inverted(l, r)` for every `l` that
+/// was before `r` and ends up after it.
+fn merge_sort(v: &mut [usize], key: ArrayView1<Fl>, inverted: &mut dyn FnMut(usize, usize)) {
+    if v.len() < 2 {
+        return;
+    }
+    let mid = v.len() / 2;
+    merge_sort(&mut v[..mid], key, inverted);
+    merge_sort(&mut v[mid..], key, inverted);
+
+    let mut merged = Vec::with_capacity(v.len());
+    let (mut i, mut j) = (0, mid);
+    while i < mid && j < v.len() {
+        if key[v[j]].total_cmp(&key[v[i]]).is_gt() {
+            v[i..mid].iter().for_each(|&l| inverted(l, v[j]));
+            merged.push(v[j]);
+            j += 1;
+        } else {
+            merged.push(v[i]);
+            i += 1;
+        }
+    }
+    merged.extend_from_slice(&v[i..mid]);
+    merged.extend_from_slice(&v[j..]);
+    v.copy_from_slice(&merged);
+}
+
+impl Prom {
+    /// Runs every leave-one-out scenario and, with `copies`, every add-a-copy scenario.
+    pub fn rank_reversal(&mut self, copies: bool) -> Result<RankReversal> {
+        let n = self.matrix_t.dim().1;
+        let mut scenarios: Vec<Scenario> = (0..n).map(Scenario::Remove).collect();
+        if copies {
+            scenarios.extend((0..n).map(Scenario::Copy));
+        }
+        self.rank_reversal_for(&scenarios)
+    }
+
+    /// Reports the pairs of alternatives that swap order in each scenario, updating the
+    /// computed flows in `O(n m)` per scenario.
+    pub fn rank_reversal_for(&mut self, scenarios: &[Scenario]) -> Result<RankReversal> {
+        let (m, n) = self.matrix_t.dim();
+        if matches!(self.missing, MissingPolicy::Valid | MissingPolicy::Exclude) {
+            return Err(MCDMRSError::Error(format!(
+                "Rank reversal diagnostics do not support the `{}` missing value policy",
+                self.missing
+            ))
+            .into());
+        }
+        if let Some(s) = scenarios.iter().find(|s| s.alternative() >= n) {
+            return Err(MCDMRSError::Error(format!(
+                "Alternative index {} is out of bounds for {} alternatives",
+                s.alternative(),
+                n
+            ))
+            .into());
+        }
+        if n < 3 && scenarios.iter().any(|s| matches!(s, Scenario::Remove(_))) {
+            return Err(MCDMRSError::Error(
+                "Removing an alternative needs at least three alternatives".to_string(),
+            )
+            .into());
+        }
+        if self.mc_flow.is_none() {
+            self.compute_multicriterion_flow()?;
+        }
+
+        let mut mat = mult_axis_0(self.matrix_t.view(), self.criteria.criteria_type.view())?;
+        if let MissingPolicy::Impute(how) = self.missing {
+            mat = impute(mat.view(), how);
+        }
+        let functions = self.criteria.preference_functions()?;
+        let weight = normalize_vec(self.criteria.weight.view());
+        let mc = self.mc_flow.as_ref().unwrap();
+        // the weighted sums of the net preferences of each alternative
+        let net_sum: Array2<Fl> =
+            (&mc.pref_matrix_plus_t - &mc.pref_matrix_minus_t) * (n - 1) as Fl;
+        let score: Array1<Fl> =
+            mult_axis_0(net_sum.view(), weight.view())?.sum_axis(Axis(0)) / (n - 1) as Fl;
+
+        let mut reports: Array1<ScenarioReport> = scenarios
+            .iter()
+            .map(|&scenario| ScenarioReport {
+                scenario,
+                score: Array1::zeros(0),
+                swaps: vec![],
+            })
+            .collect();
+        zip_for_each!(
+            &self.options,
+            Zip::from(&mut reports),
+            |report: &mut ScenarioReport| {
+                let k = report.scenario.alternative();
+                let (sign, denominator) = match report.scenario {
+                    Scenario::Remove(_) => (-1.0, (n - 2) as Fl),
+                    Scenario::Copy(_) => (1.0, n as Fl),
+                };
+                let mut after = Array1::<Fl>::zeros(n);
+                for j in 0..m {
+                    let (row, f) = (mat.row(j), &functions[j]);
+                    let (q, p, w) = (&self.criteria.q[j], &self.criteria.p[j], weight[j]);
+                    for (a, s) in after.iter_mut().enumerate() {
+                        let d = row[a] - row[k];
+                        let net = f.preference(&d, q, p) - f.preference(&-d, q, p);
+                        *s += w * (net_sum[[j, a]] + sign * net);
+                    }
+                }
+                after /= denominator;
+                if let Scenario::Remove(_) = report.scenario {
+                    after[k] = Fl::NAN;
+                }
+                report.swaps = swaps(score.view(), after.view());
+                report.score = after;
+            }
+        );
+
+        Ok(RankReversal {
+            score,
+            scenarios: reports.to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::promethee::Criteria;
+    use super::super::utils::test_prom;
+    use super::*;
+    use is_close::all_close;
+    use ndarray::{array, s};
+
+    fn get_prom() -> Prom {
+        test_prom(array![[4., 3., 1., 0.], [5., 4., 3., 5.]], array![1., 1.])
+    }
+
+    #[test]
+    fn test_leave_one_out() -> Result<()> {
+        let mut p = get_prom();
+        let rr = p.rank_reversal(false)?;
+        assert!(rr.has_reversal());
+        // without 2, alternative 1 falls behind 3
+        let reversals: Vec<&ScenarioReport> = rr.reversals().collect();
+        assert_eq!(1, reversals.len());
+        assert_eq!(Scenario::Remove(2), reversals[0].scenario);
+        let swap = reversals[0].swaps[0];
+        assert_eq!((1, 3), (swap.a, swap.b));
+        assert!(all_close!(
+            vec![1. / 6., -0.25],
+            vec![swap.before, swap.after],
+            abs_tol = 1e-6
+        ));
+        assert!(reversals[0].score[2].is_nan());
+        Ok(())
+    }
+
+    #[test]
+    fn test_matches_recompute() -> Result<()> {
+        use rand::Rng;
+        let mut rng = rand::thread_rng();
+        let matrix = Array2::from_shape_fn((3, 12), |_| rng.gen_range(0..6) as Fl);
+        let criteria = Criteria::new(
+            array![0.5, 0.3, 0.2],
+            array![1., -1., 1.],
+            array![
+                "usual".to_string(),
+                "linear".to_string(),
+                "level".to_string()
+            ],
+            array![0., 1., 0.5],
+            array![0., 3., 2.],
+        )?;
+        let mut p = Prom::new(matrix.clone(), criteria.clone())?;
+        p.compute_prom_ii()?;
+        let rr = p.rank_reversal(true)?;
+        assert!(all_close!(
+            p.prom_ii.as_ref().unwrap().score.to_vec(),
+            rr.score.to_vec(),
+            abs_tol = 1e-5
+        ));
+
+        for report in &rr.scenarios {
+            let (full, keep): (Array2<Fl>, Vec<usize>) = match report.scenario {
+                Scenario::Remove(k) => {
+                    let keep: Vec<usize> = (0..12).filter(|&i| i != k).collect();
+                    (matrix.select(Axis(1), &keep), keep)
+                }
+                Scenario::Copy(k) => {
+                    let mut cols: Vec<usize> = (0..12).collect();
+                    cols.push(k);
+                    (matrix.select(Axis(1), &cols), (0..12).collect())
+                }
+            };
+            let mut q = Prom::new(full, criteria.clone())?;
+            q.compute_prom_ii()?;
+            let expected = q.prom_ii.unwrap().score;
+            let got = report.score.select(Axis(0), &keep);
+            assert!(all_close!(
+                expected.slice(s![..keep.len()]).to_vec(),
+                got.to_vec(),
+                abs_tol = 1e-5
+            ));
+
+            for &i in &keep {
+                for &j in &keep {
+                    let (before, after) = (&rr.score, &report.score);
+                    let flipped = gt(&before[i], &before[j]) && lt(&after[i], &after[j]);
+                    let swap = report.swaps.iter().any(|s| (s.a, s.b) == (i, j));
+                    assert_eq!(flipped, swap, "{:?} {} {}", report.scenario, i, j);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_scenarios() {
+        let mut p = get_prom();
+        assert!(p.rank_reversal_for(&[Scenario::Copy(4)]).is_err());
+        let mut p = p.with_missing_policy(MissingPolicy::Exclude);
+        assert!(p.rank_reversal(false).is_err());
+    }
+}
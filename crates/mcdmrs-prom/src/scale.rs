@@ -0,0 +1,160 @@
+use super::types::{Fl, MCDMRSError, Result};
+use ndarray::Array1;
+use std::fmt;
+use std::str::FromStr;
+
+/// An ordered list of labels for a qualitative criterion, from least to most.
+///
+/// Each label maps to a numeric value, which defaults to its position in the scale
+/// (0, 1, 2, ...). Scales are written as `low|medium|high`, or with explicit values as
+/// `low=0|medium=1|high=5`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrdinalScale {
+    pub labels: Vec<String>,
+    pub values: Vec<Fl>,
+}
+
+fn check_scale(labels: &[String], values: &[Fl]) -> std::result::Result<(), String> {
+    if labels.is_empty() || labels.len() != values.len() {
+        return Err("an ordinal scale needs at least one label and one value per label".into());
+    }
+    match labels
+        .iter()
+        .enumerate()
+        .find_map(|(i, l)| labels[..i].contains(l).then_some(l))
+    {
+        Some(dup) => Err(format!("label `{}` appears more than once", dup)),
+        None => Ok(()),
+    }
+}
+
+impl OrdinalScale {
+    pub fn new(labels: Vec<String>, values: Option<Vec<Fl>>) -> Result<OrdinalScale> {
+        let values = values.unwrap_or_else(|| (0..labels.len()).map(|i| i as Fl).collect());
+        check_scale(&labels, &values).map_err(MCDMRSError::Error)?;
+
+        Ok(OrdinalScale { labels, values })
+    }
+
+    pub fn value_of(&self, label: &str) -> Option<Fl> {
+        self.labels
+            .iter()
+            .position(|l| l == label)
+            .map(|i| self.values[i])
+    }
+
+    /// Maps each label of `criterion` to its value. Missing labels become NaN.
+    pub fn map_labels<'a, I>(&self, criterion: &str, labels: I) -> Result<Array1<Fl>>
+    where
+        I: IntoIterator<Item = Option<&'a str>>,
+    {
+        labels
+            .into_iter()
+            .map(|l| match l {
+                Some(l) => self.value_of(l).ok_or(
+                    MCDMRSError::Error(format!(
+                        "unknown label `{}` for criterion `{}`, expected one of {}",
+                        l,
+                        criterion,
+                        self.labels.join(", ")
+                    ))
+                    .into(),
+                ),
+                None => Ok(Fl::NAN),
+            })
+            .collect()
+    }
+}
+
+impl FromStr for OrdinalScale {
+    type Err = MCDMRSError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let parts: Vec<(&str, Option<&str>)> = s
+            .split('|')
+            .map(|part| match part.split_once('=') {
+                Some((label, value)) => (label.trim(), Some(value.trim())),
+                None => (part.trim(), None),
+            })
+            .collect();
+
+        let values = if parts.iter().all(|(_, v)| v.is_some()) {
+            let values = parts
+                .iter()
+                .map(|(_, v)| v.unwrap_or_default().parse::<Fl>())
+                .collect::<std::result::Result<Vec<Fl>, _>>()
+                .map_err(|e| MCDMRSError::Error(format!("invalid ordinal scale `{}`: {}", s, e)))?;
+            Some(values)
+        } else if parts.iter().all(|(_, v)| v.is_none()) {
+            None
+        } else {
+            return Err(MCDMRSError::Error(format!(
+                "invalid ordinal scale `{}`: give a value for every label or for none",
+                s
+            )));
+        };
+
+        let labels: Vec<String> = parts.iter().map(|(l, _)| l.to_string()).collect();
+        let values = values.unwrap_or_else(|| (0..labels.len()).map(|i| i as Fl).collect());
+        check_scale(&labels, &values)
+            .map_err(|e| MCDMRSError::Error(format!("invalid ordinal scale `{}`: {}", s, e)))?;
+
+        Ok(OrdinalScale { labels, values })
+    }
+}
+
+impl fmt::Display for OrdinalScale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self
+            .labels
+            .iter()
+            .zip(self.values.iter())
+            .map(|(l, v)| format!("{}={}", l, v))
+            .collect();
+        f.write_str(&parts.join("|"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn test_parse() {
+        let scale: OrdinalScale = "low|medium|high".parse().unwrap();
+        assert_eq!(vec![0., 1., 2.], scale.values);
+
+        let scale: OrdinalScale = "low=0 | medium=1 | high=5".parse().unwrap();
+        assert_eq!(vec!["low", "medium", "high"], scale.labels);
+        assert_eq!(Some(5.0), scale.value_of("high"));
+        assert_eq!(None, scale.value_of("extreme"));
+        assert_eq!(scale, scale.to_string().parse().unwrap());
+
+        assert!("low=0|medium".parse::<OrdinalScale>().is_err());
+        assert!("low=a|medium=1".parse::<OrdinalScale>().is_err());
+        assert!("low|low".parse::<OrdinalScale>().is_err());
+        assert!(OrdinalScale::new(vec!["low".to_string()], Some(vec![])).is_err());
+    }
+
+    #[test]
+    fn test_map_labels() {
+        let scale: OrdinalScale = "low|medium|high".parse().unwrap();
+        let values = scale
+            .map_labels("risk", [Some("high"), None, Some("low")])
+            .unwrap();
+        assert_eq!(2.0, values[0]);
+        assert!(values[1].is_nan());
+        assert_eq!(0.0, values[2]);
+
+        let err = scale.map_labels("risk", [Some("extreme")]).unwrap_err();
+        assert!(err.to_string().contains(
+            "unknown label `extreme` for criterion `risk`, expected one of low, medium, high"
+        ));
+        assert_eq!(
+            array![1.],
+            scale.map_labels("risk", [Some("medium")]).unwrap()
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use super::promethee::Prom;
+use super::types::{Fl, MCDMRSError, Result};
+use ndarray::{Array1, Array2, ArrayView1, Axis};
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeightSweep {
+    pub criterion: usize,
+    pub weight: Array1<Fl>,
+    pub score: Array2<Fl>,
+}
+
+/// Re-runs Promethee II once per value in `weights` for a single criterion.
+///
+/// The preference matrices are computed once and reused for every step, and the
+/// original weights are restored afterwards. Row `i` of `score` holds the net flows
+/// when the criterion weight is `weights[i]` (before normalization).
+pub fn weight_sweep(
+    p: &mut Prom,
+    criterion: usize,
+    weights: ArrayView1<Fl>,
+) -> Result<WeightSweep> {
+    if criterion >= p.criteria.weight.len() {
+        return Err(MCDMRSError::Error(format!(
+            "Criterion index {} is out of bounds for {} criteria",
+            criterion,
+            p.criteria.weight.len()
+        ))
+        .into());
+    }
+
+    let original = p.criteria.weight.clone();
+    let hierarchy = p.criteria.hierarchy.clone();
+    let mut score = Array2::<Fl>::zeros((weights.len(), p.matrix_t.dim().1));
+
+    for (w, mut row) in weights.iter().zip(score.axis_iter_mut(Axis(0))) {
+        let mut weight = original.clone();
+        weight[criterion] = *w;
+        p.re_weight(weight.view())?;
+        if let Some(pii) = &p.prom_ii {
+            row.assign(&pii.score);
+        }
+    }
+    p.re_weight(original.view())?;
+    p.criteria.hierarchy = hierarchy;
+
+    Ok(WeightSweep {
+        criterion,
+        weight: weights.to_owned(),
+        score,
+    })
+}
+
+impl Prom {
+    pub fn weight_sweep(
+        &mut self,
+        criterion: usize,
+        weights: ArrayView1<Fl>,
+    ) -> Result<WeightSweep> {
+        weight_sweep(self, criterion, weights)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::utils::get_prom;
+    use is_close::all_close;
+    use ndarray::array;
+
+    #[test]
+    fn test_weight_sweep() {
+        let mut p = get_prom();
+        let sweep = p.weight_sweep(0, array![0., 1., 3.].view()).unwrap();
+
+        assert_eq!((3, 3), sweep.score.dim());
+        // with zero weight only the second criterion matters
+        assert!(all_close!(
+            vec![-1., 1., 0.],
+            sweep.score.row(0).to_vec(),
+            abs_tol = 1e-6
+        ));
+        assert!(all_close!(
+            vec![-1., 0.5, 0.5],
+            sweep.score.row(1).to_vec(),
+            abs_tol = 1e-6
+        ));
+        // original weights are restored
+        assert_eq!(array![1., 1.], p.criteria.weight);
+        assert!(all_close!(
+            vec![-1., 0.5, 0.5],
+            p.prom_ii.unwrap().score.to_vec(),
+            abs_tol = 1e-6
+        ));
+    }
+
+    #[test]
+    fn test_weight_sweep_bad_criterion() {
+        let mut p = get_prom();
+        assert!(p.weight_sweep(2, array![1.].view()).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+//! Branch-free, lane-wise preference functions for the unicriterion flow kernels.
+//!
+//! Each function matches its scalar counterpart in `pref_functions`, including the
+//! relative tolerance of the comparisons. Missing values produce NaN differences,
+//! which every function maps to 0 (indifference).
+use super::cmp::REL_TOL;
+use super::types::Fl;
+use wide::{f32x8, CmpGt, CmpLe};
+
+pub const LANES: usize = 8;
+
+/// Lane-wise [`super::cmp::gt`].
+#[inline(always)]
+fn gt(a: f32x8, b: f32x8) -> f32x8 {
+    let close = (a - b).abs().cmp_le(a.abs().max(b.abs()) * REL_TOL);
+    a.cmp_gt(b) & !close
+}
+
+#[inline(always)]
+pub fn usual(d: f32x8, _q: f32x8, _p: f32x8) -> f32x8 {
+    gt(d, f32x8::ZERO).blend(f32x8::ONE, f32x8::ZERO)
+}
+
+#[inline(always)]
+pub fn ushape(d: f32x8, q: f32x8, _p: f32x8) -> f32x8 {
+    gt(d, q).blend(f32x8::ONE, f32x8::ZERO)
+}
+
+#[inline(always)]
+pub fn vshape(d: f32x8, _q: f32x8, p: f32x8) -> f32x8 {
+    let linear = gt(d, f32x8::ZERO).blend(d / p, f32x8::ZERO);
+    gt(d, p).blend(f32x8::ONE, linear)
+}
+
+#[inline(always)]
+pub fn vshape2(d: f32x8, q: f32x8, p: f32x8) -> f32x8 {
+    let linear = gt(d, q).blend((d - q) / (p - q), f32x8::ZERO);
+    gt(d, p).blend(f32x8::ONE, linear)
+}
+
+#[inline(always)]
+pub fn level(d: f32x8, q: f32x8, p: f32x8) -> f32x8 {
+    let half = gt(d, q).blend(f32x8::HALF, f32x8::ZERO);
+    gt(d, p).blend(f32x8::ONE, half)
+}
+
+/// Returns the sums of `f(v1 - v2)` and `f(v2 - v1)` over every `v2` in `values`.
+#[inline(always)]
+pub fn flow_sums<F>(v1: Fl, values: &[Fl], q: Fl, p: Fl, f: F) -> (Fl, Fl)
+where
+    F: Fn(f32x8, f32x8, f32x8) -> f32x8,
+{
+    let (v1, q, p) = (f32x8::splat(v1), f32x8::splat(q), f32x8::splat(p));
+    let (mut plus, mut minus) = (f32x8::ZERO, f32x8::ZERO);
+
+    let mut lanes = |v2: [Fl; LANES]| {
+        let diff = v1 - f32x8::from(v2);
+        plus += f(diff, q, p);
+        minus += f(-diff, q, p);
+    };
+
+    let chunks = values.chunks_exact(LANES);
+    let rest = chunks.remainder();
+    for chunk in chunks {
+        lanes(chunk.try_into().unwrap());
+    }
+    if !rest.is_empty() {
+        // the padding lanes are missing values and add nothing
+        let mut tail = [Fl::NAN; LANES];
+        tail[..rest.len()].copy_from_slice(rest);
+        lanes(tail);
+    }
+
+    (plus.reduce_add(), minus.reduce_add())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::pref_functions;
+    use super::*;
+
+    #[test]
+    fn test_matches_scalar() {
+        let values: Vec<Fl> = vec![
+            -1.0,
+            -0.5,
+            0.0,
+            1e-9,
+            0.1,
+            0.2,
+            0.2 + 1e-8,
+            0.25,
+            0.4,
+            0.5,
+            0.8,
+            1.0,
+            Fl::NAN,
+        ];
+        let (q, p) = (0.2, 0.5);
+
+        type Scalar = fn(&Fl, &Fl, &Fl) -> Fl;
+        type Lanes = fn(f32x8, f32x8, f32x8) -> f32x8;
+        let functions: [(Scalar, Lanes); 5] = [
+            (pref_functions::usual, usual),
+            (pref_functions::ushape, ushape),
+            (pref_functions::vshape, vshape),
+            (pref_functions::vshape2, vshape2),
+            (pref_functions::level, level),
+        ];
+
+        for (scalar, lanes) in functions {
+            for &v1 in &values[..values.len() - 1] {
+                let expected =
+                    values
+                        .iter()
+                        .filter(|v2| !v2.is_nan())
+                        .fold((0.0, 0.0), |(pl, mi), v2| {
+                            (
+                                pl + scalar(&(v1 - v2), &q, &p),
+                                mi + scalar(&(v2 - v1), &q, &p),
+                            )
+                        });
+                let (pl, mi) = flow_sums(v1, &values, q, p, lanes);
+                assert!((expected.0 - pl).abs() < 1e-5);
+                assert!((expected.1 - mi).abs() < 1e-5);
+            }
+        }
+    }
+}
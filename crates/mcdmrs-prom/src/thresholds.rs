@@ -0,0 +1,102 @@
+use super::math::quantile;
+use super::types::{Fl, MCDMRSError, Result};
+use ndarray::{Array1, ArrayView1, ArrayView2, Axis};
+
+/// Maximum number of values per criterion used to build the pairwise difference
+/// distribution. Larger inputs are reduced to evenly spaced order statistics.
+const MAX_SAMPLE: usize = 1000;
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThresholdSuggestion {
+    pub q: Array1<Fl>,
+    pub p: Array1<Fl>,
+}
+
+fn sample_sorted(array: ArrayView1<Fl>) -> Vec<Fl> {
+    let mut values: Vec<Fl> = array.iter().copied().filter(|v| !v.is_nan()).collect();
+    values.sort_by(|a, b| a.total_cmp(b));
+
+    if values.len() <= MAX_SAMPLE {
+        return values;
+    }
+    let step = (values.len() - 1) as f64 / (MAX_SAMPLE - 1) as f64;
+    (0..MAX_SAMPLE)
+        .map(|i| values[(i as f64 * step).round() as usize])
+        .collect()
+}
+
+fn pairwise_differences(values: &[Fl]) -> Array1<Fl> {
+    let mut diffs = Vec::with_capacity(values.len() * values.len().saturating_sub(1) / 2);
+    for (i, a) in values.iter().enumerate() {
+        for b in values[i + 1..].iter() {
+            diffs.push((a - b).abs());
+        }
+    }
+    Array1::from_vec(diffs)
+}
+
+/// Suggests indifference (`q`) and preference (`p`) thresholds for each criterion.
+///
+/// The thresholds are the `q_quantile` and `p_quantile` of the absolute pairwise
+/// differences between alternatives on each criterion, ignoring missing values.
+pub fn suggest_thresholds(
+    matrix_t: ArrayView2<Fl>,
+    q_quantile: Fl,
+    p_quantile: Fl,
+) -> Result<ThresholdSuggestion> {
+    let in_range = (0.0..=1.0).contains(&q_quantile) && (0.0..=1.0).contains(&p_quantile);
+    if !in_range || q_quantile > p_quantile {
+        return Err(MCDMRSError::Error(
+            "Quantiles must satisfy 0 <= q_quantile <= p_quantile <= 1".to_string(),
+        )
+        .into());
+    }
+
+    let (m, _) = matrix_t.dim();
+    let mut q = Array1::<Fl>::zeros(m);
+    let mut p = Array1::<Fl>::zeros(m);
+
+    for (j, row) in matrix_t.axis_iter(Axis(0)).enumerate() {
+        let diffs = pairwise_differences(&sample_sorted(row));
+        q[j] = quantile(diffs.view(), q_quantile);
+        p[j] = quantile(diffs.view(), p_quantile);
+    }
+
+    Ok(ThresholdSuggestion { q, p })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn test_suggest_thresholds() {
+        let mat = array![[0., 1., 2., 3.], [0., 10., Fl::NAN, 30.]];
+        let s = suggest_thresholds(mat.view(), 0.0, 1.0).unwrap();
+
+        assert_eq!(array![1., 10.], s.q);
+        assert_eq!(array![3., 30.], s.p);
+
+        let s = suggest_thresholds(mat.view(), 0.5, 0.5).unwrap();
+        assert_eq!(s.q, s.p);
+    }
+
+    #[test]
+    fn test_suggest_thresholds_errors() {
+        let mat = array![[0., 1., 2., 3.]];
+        assert!(suggest_thresholds(mat.view(), 0.8, 0.2).is_err());
+        assert!(suggest_thresholds(mat.view(), -0.1, 0.2).is_err());
+    }
+
+    #[test]
+    fn test_sample_sorted() {
+        let arr = Array1::<Fl>::from_iter((0..5000).rev().map(|i| i as Fl));
+        let s = sample_sorted(arr.view());
+
+        assert_eq!(MAX_SAMPLE, s.len());
+        assert_eq!(0.0, s[0]);
+        assert_eq!(4999.0, s[MAX_SAMPLE - 1]);
+    }
+}
@@ -1,3 +1,4 @@
+use alloc::vec::Vec;
 use ndarray::{Array2, Axis};
 
 pub use mcdmrs_error::{AnyError, MCDMRSError, Result};
@@ -1,3 +1,4 @@
+use super::compute::{zip_for_each, ComputeOptions};
 use super::pref_functions::*;
 use super::types::Fl;
 use ndarray::{ArrayView1, ArrayViewMut1, Zip};
@@ -14,10 +15,10 @@ pub fn _unicriterion_flow(
 
     let func = _get_pref_function(fname);
 
-    Zip::from(array)
-        .and(plus)
-        .and(minus)
-        .par_for_each(|&v1, pl, mi| {
+    zip_for_each!(
+        &ComputeOptions::Global,
+        Zip::from(array).and(plus).and(minus),
+        |&v1: &Fl, pl: &mut Fl, mi: &mut Fl| {
             for v2 in array.iter() {
                 let diff = v1 - v2;
                 let ndiff = -diff;
@@ -26,17 +27,29 @@ pub fn _unicriterion_flow(
             }
             *pl /= n;
             *mi /= n;
-        });
+        }
+    );
 }
 
 macro_rules! build_unicriterion_flow_fn {
-    ($wrapper_name:ident, $alg:expr ) => {
+    ($wrapper_name:ident, $options_name:ident, $alg:ident ) => {
         pub fn $wrapper_name(
             array: ArrayView1<Fl>,
             plus: ArrayViewMut1<Fl>,
             minus: ArrayViewMut1<Fl>,
             q: &Fl,
             p: &Fl,
+        ) {
+            $options_name(array, plus, minus, q, p, &ComputeOptions::Global)
+        }
+
+        pub fn $options_name(
+            array: ArrayView1<Fl>,
+            plus: ArrayViewMut1<Fl>,
+            minus: ArrayViewMut1<Fl>,
+            q: &Fl,
+            p: &Fl,
+            options: &ComputeOptions,
         ) {
             // when built with rayon this optimizes using loop unrolling. When built without
             // rayon, this optimizes into 4 lane SIMD.
@@ -44,13 +57,27 @@ macro_rules! build_unicriterion_flow_fn {
             // benchmark.
             // SIMD alone (without parallelism) results in a 70% drop in performance for the
             // multicriteria benchmark.
+            // With the `simd` feature the inner loop uses the branch-free 8 lane kernels
+            // instead, keeping rayon for the outer loop.
             let n: Fl = array.len() as Fl - 1.0;
 
-            Zip::from(array)
-                .and(plus)
-                .and(minus)
-                .par_for_each(|&v1, pl, mi| {
+            #[cfg(feature = "simd")]
+            let values = array.to_vec();
+
+            zip_for_each!(
+                options,
+                Zip::from(array).and(plus).and(minus),
+                |&v1: &Fl, pl: &mut Fl, mi: &mut Fl| {
                     if !v1.is_nan() {
+                        #[cfg(feature = "simd")]
+                        {
+                            let (sum_plus, sum_minus) =
+                                super::simd::flow_sums(v1, &values, *q, *p, super::simd::$alg);
+                            *pl += sum_plus;
+                            *mi += sum_minus;
+                        }
+
+                        #[cfg(not(feature = "simd"))]
                         for v2 in array.iter() {
                             if v2.is_nan() {
                                 continue;
@@ -63,19 +90,67 @@ macro_rules! build_unicriterion_flow_fn {
                             *pl += $alg(&diff, q, p);
                             *mi += $alg(&ndiff, q, p);
                         }
+
                         *pl /= n;
                         *mi /= n;
                     }
-                });
+                }
+            );
         }
     };
 }
 
-build_unicriterion_flow_fn!(unicriterion_flow_usual, usual);
-build_unicriterion_flow_fn!(unicriterion_flow_ushape, ushape);
-build_unicriterion_flow_fn!(unicriterion_flow_vshape, vshape);
-build_unicriterion_flow_fn!(unicriterion_flow_vshape2, vshape2);
-build_unicriterion_flow_fn!(unicriterion_flow_level, level);
+build_unicriterion_flow_fn!(unicriterion_flow_usual, unicriterion_flow_usual_with, usual);
+build_unicriterion_flow_fn!(
+    unicriterion_flow_ushape,
+    unicriterion_flow_ushape_with,
+    ushape
+);
+build_unicriterion_flow_fn!(
+    unicriterion_flow_vshape,
+    unicriterion_flow_vshape_with,
+    vshape
+);
+build_unicriterion_flow_fn!(
+    unicriterion_flow_vshape2,
+    unicriterion_flow_vshape2_with,
+    vshape2
+);
+build_unicriterion_flow_fn!(unicriterion_flow_level, unicriterion_flow_level_with, level);
+
+/// The unicriterion flow of a user-defined preference function. Missing values are
+/// skipped as in the built-in kernels.
+pub fn unicriterion_flow_dyn(
+    array: ArrayView1<Fl>,
+    plus: ArrayViewMut1<Fl>,
+    minus: ArrayViewMut1<Fl>,
+    func: &dyn PreferenceFunction,
+    q: &Fl,
+    p: &Fl,
+    options: &ComputeOptions,
+) {
+    let n: Fl = array.len() as Fl - 1.0;
+
+    zip_for_each!(
+        options,
+        Zip::from(array).and(plus).and(minus),
+        |&v1: &Fl, pl: &mut Fl, mi: &mut Fl| {
+            if !v1.is_nan() {
+                for v2 in array.iter() {
+                    if v2.is_nan() {
+                        continue;
+                    }
+                    let diff = v1 - v2;
+                    let ndiff = -diff;
+                    *pl += func.preference(&diff, q, p);
+                    *mi += func.preference(&ndiff, q, p);
+                }
+                *pl /= n;
+                *mi /= n;
+            }
+        }
+    );
+}
 
 #[cfg(test)]
 mod test {
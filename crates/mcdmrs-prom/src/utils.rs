@@ -25,10 +25,40 @@ pub fn generate_prom(n: usize, m: usize) -> Result<Prom> {
             pref_function: Array1::<String>::from(vec!["usual".to_string(); len]),
             q: Array1::<Fl>::from(vec![0.; len]),
             p: Array1::<Fl>::from(vec![0.; len]),
+            name: None,
+            scale: None,
+            functions: Default::default(),
+            hierarchy: None,
+            weight_constraints: vec![],
         },
     )
 }
 
+/// Returns a test analysis of `matrix_t` with unit weights, the usual preference
+/// function and zero thresholds on every criterion.
+#[cfg(test)]
+pub(crate) fn test_prom(matrix_t: Array2<Fl>, criteria_type: Array1<Fl>) -> Prom {
+    let m = matrix_t.dim().0;
+    let criteria = Criteria::new(
+        Array1::ones(m),
+        criteria_type,
+        Array1::from_elem(m, "usual".to_string()),
+        Array1::zeros(m),
+        Array1::zeros(m),
+    )
+    .unwrap();
+    Prom::new(matrix_t, criteria).unwrap()
+}
+
+/// Returns the cost (minimize) and area (maximize) of three test alternatives.
+#[cfg(test)]
+pub(crate) fn get_prom() -> Prom {
+    test_prom(
+        ndarray::array![[0.8, 0.2, 0.05], [0.1, 0.6, 0.4]],
+        ndarray::array![-1., 1.],
+    )
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -0,0 +1,358 @@
+//! Validation of criteria tables, reporting every problem at once before the table is
+//! turned into [`Criteria`](super::Criteria).
+use super::pref_functions::{is_pref_function, PREF_FUNCTIONS};
+use super::scale::OrdinalScale;
+use super::types::{Fl, MCDMRSError, Result};
+use polars::prelude::{DataFrame, DataType};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The columns every criteria table must have. `scale` is optional.
+pub const REQUIRED_COLUMNS: [&str; 6] =
+    ["name", "weight", "criteria_type", "pref_function", "q", "p"];
+
+/// A problem with a criteria table. Rows count the data rows from 1, without the
+/// header, and are `None` for problems with the whole table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Issue {
+    pub row: Option<usize>,
+    pub column: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.row, &self.column) {
+            (Some(row), Some(column)) => {
+                write!(f, "row {}, column `{}`: {}", row, column, self.message)
+            }
+            (Some(row), None) => write!(f, "row {}: {}", row, self.message),
+            (None, Some(column)) => write!(f, "column `{}`: {}", column, self.message),
+            (None, None) => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Every problem found in a criteria table, see [`validate_criteria`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Returns the report as an error listing every problem, unless it is valid.
+    pub fn into_result(self) -> Result<()> {
+        if self.is_valid() {
+            Ok(())
+        } else {
+            Err(MCDMRSError::Error(self.to_string()).into())
+        }
+    }
+
+    fn push(&mut self, row: Option<usize>, column: Option<&str>, message: String) {
+        self.issues.push(Issue {
+            row,
+            column: column.map(str::to_string),
+            message,
+        });
+    }
+
+    /// Reads `column` as trimmed strings, `None` when the table does not have it.
+    fn strings(&mut self, df: &DataFrame, column: &str) -> Option<Vec<Option<String>>> {
+        let ser = df.column(column).ok()?;
+        match ser.cast(&DataType::String) {
+            Ok(ser) => Some(
+                ser.str()
+                    .ok()?
+                    .into_iter()
+                    .map(|v| v.map(|v| v.trim().to_string()).filter(|v| !v.is_empty()))
+                    .collect(),
+            ),
+            Err(e) => {
+                self.push(None, Some(column), format!("cannot be read as text: {}", e));
+                None
+            }
+        }
+    }
+
+    /// Reads `column` as numbers, reporting the missing and non-numeric values.
+    fn numbers(&mut self, df: &DataFrame, column: &str) -> Option<Vec<Option<Fl>>> {
+        let values = self.strings(df, column)?;
+        let numbers = values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let v = match v {
+                    Some(v) => v,
+                    None => {
+                        self.push(Some(i + 1), Some(column), "missing value".to_string());
+                        return None;
+                    }
+                };
+                match v.parse::<Fl>() {
+                    Ok(x) if x.is_finite() => Some(x),
+                    _ => {
+                        self.push(
+                            Some(i + 1),
+                            Some(column),
+                            format!("`{}` is not a number", v),
+                        );
+                        None
+                    }
+                }
+            })
+            .collect();
+        Some(numbers)
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_valid() {
+            return write!(f, "the criteria are valid");
+        }
+        write!(f, "found {} problems in the criteria:", self.issues.len())?;
+        for issue in &self.issues {
+            write!(f, "\n  {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks a criteria table: the required columns, that the numeric columns hold
+/// numbers, the preference function names, non-negative weights and thresholds,
+/// `q <= p` for the functions using both thresholds, the ordinal scales and, when
+/// `alternatives` lists the columns of the alternatives table, that every criterion
+/// is one of them.
+///
+/// Custom preference functions registered with
+/// [`Criteria::with_function`](super::Criteria::with_function) are reported as unknown.
+pub fn validate_criteria(
+    criteria_df: &DataFrame,
+    alternatives: Option<&[&str]>,
+) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    for column in REQUIRED_COLUMNS {
+        if criteria_df.column(column).is_err() {
+            report.push(None, Some(column), "required column is missing".to_string());
+        }
+    }
+
+    if let Some(names) = report.strings(criteria_df, "name") {
+        let mut seen = HashMap::new();
+        for (i, name) in names.iter().enumerate() {
+            let name = match name {
+                Some(name) => name,
+                None => {
+                    report.push(Some(i + 1), Some("name"), "missing value".to_string());
+                    continue;
+                }
+            };
+            if let Some(first) = seen.insert(name.clone(), i + 1) {
+                report.push(
+                    Some(i + 1),
+                    Some("name"),
+                    format!("`{}` is already defined in row {}", name, first),
+                );
+            }
+            if alternatives.is_some_and(|columns| !columns.contains(&name.as_str())) {
+                report.push(
+                    Some(i + 1),
+                    Some("name"),
+                    format!("`{}` is not a column of the alternatives", name),
+                );
+            }
+        }
+    }
+
+    if let Some(weights) = report.numbers(criteria_df, "weight") {
+        for (i, w) in weights.iter().enumerate() {
+            if w.is_some_and(|w| w < 0.0) {
+                report.push(
+                    Some(i + 1),
+                    Some("weight"),
+                    format!("must not be negative, got {}", w.unwrap_or_default()),
+                );
+            }
+        }
+        if !weights.is_empty() && weights.iter().all(|w| *w == Some(0.0)) {
+            report.push(None, Some("weight"), "the weights are all 0".to_string());
+        }
+    }
+
+    if let Some(types) = report.numbers(criteria_df, "criteria_type") {
+        for (i, t) in types.iter().enumerate() {
+            if t.is_some_and(|t| t != 1.0 && t != -1.0) {
+                report.push(
+                    Some(i + 1),
+                    Some("criteria_type"),
+                    format!(
+                        "must be 1 (maximize) or -1 (minimize), got {}",
+                        t.unwrap_or_default()
+                    ),
+                );
+            }
+        }
+    }
+
+    let functions = report.strings(criteria_df, "pref_function");
+    if let Some(functions) = &functions {
+        for (i, f) in functions.iter().enumerate() {
+            match f {
+                None => report.push(
+                    Some(i + 1),
+                    Some("pref_function"),
+                    "missing value".to_string(),
+                ),
+                Some(f) if !is_pref_function(f) => report.push(
+                    Some(i + 1),
+                    Some("pref_function"),
+                    format!(
+                        "unknown preference function `{}`, expected one of {}",
+                        f,
+                        PREF_FUNCTIONS.join(", ")
+                    ),
+                ),
+                _ => {}
+            }
+        }
+    }
+
+    let q = report.numbers(criteria_df, "q");
+    let p = report.numbers(criteria_df, "p");
+    for (column, values) in [("q", &q), ("p", &p)] {
+        for (i, v) in values.iter().flatten().enumerate() {
+            if v.is_some_and(|v| v < 0.0) {
+                report.push(
+                    Some(i + 1),
+                    Some(column),
+                    format!("must not be negative, got {}", v.unwrap_or_default()),
+                );
+            }
+        }
+    }
+    if let (Some(q), Some(p), Some(functions)) = (&q, &p, &functions) {
+        for (i, ((q, p), f)) in q.iter().zip(p).zip(functions).enumerate() {
+            let uses_both = f
+                .as_deref()
+                .is_some_and(|f| ["vshape2", "vshape_2", "linear", "level"].contains(&f));
+            if let (Some(q), Some(p), true) = (q, p, uses_both) {
+                if q > p {
+                    report.push(
+                        Some(i + 1),
+                        Some("q"),
+                        format!(
+                            "q ({}) must not exceed p ({}) for `{}`",
+                            q,
+                            p,
+                            f.as_deref().unwrap_or_default()
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    if let Some(scales) = report.strings(criteria_df, "scale") {
+        for (i, scale) in scales.iter().enumerate() {
+            if let Some(Err(MCDMRSError::Error(e))) =
+                scale.as_deref().map(str::parse::<OrdinalScale>)
+            {
+                report.push(Some(i + 1), Some("scale"), e);
+            }
+        }
+    }
+
+    report.issues.sort_by_key(|issue| issue.row);
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use polars::prelude::*;
+
+    fn criteria_df() -> PolarsResult<DataFrame> {
+        df!(
+            "name"=> &[Some("cost"), Some("area"), Some("cost"), None],
+            "weight" => &["2", "-1", "x", "1"],
+            "criteria_type" => &[-1., 1., 2., 1.],
+            "pref_function" => &[Some("linear"), Some("gaussian"), None, Some("level")],
+            "q" => &[5., 0., -1., 0.],
+            "p" => &[1., 0., 0., 1.],
+            "scale" => &[None, Some("low|low"), None, None],
+        )
+    }
+
+    #[test]
+    fn test_valid() -> Result<()> {
+        let df = df!(
+            "name"=> &["cost", "risk"],
+            "weight" => &[2, 1],
+            "criteria_type" => &[-1, -1],
+            "pref_function" => &["linear", "usual"],
+            "q" => &[1., 0.],
+            "p" => &[5., 0.],
+            "scale" => &[None, Some("low|medium|high")],
+        )?;
+        let report = validate_criteria(&df, Some(&["id", "cost", "risk"]));
+        assert!(report.is_valid(), "{}", report);
+        assert!(report.into_result().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_report() -> Result<()> {
+        let report = validate_criteria(&criteria_df()?, Some(&["cost"]));
+        let found: Vec<(Option<usize>, Option<&str>)> = report
+            .issues
+            .iter()
+            .map(|i| (i.row, i.column.as_deref()))
+            .collect();
+        assert_eq!(
+            vec![
+                (Some(1), Some("q")),
+                (Some(2), Some("name")),
+                (Some(2), Some("weight")),
+                (Some(2), Some("pref_function")),
+                (Some(2), Some("scale")),
+                (Some(3), Some("name")),
+                (Some(3), Some("weight")),
+                (Some(3), Some("criteria_type")),
+                (Some(3), Some("pref_function")),
+                (Some(3), Some("q")),
+                (Some(4), Some("name")),
+            ],
+            found
+        );
+
+        let message = report.to_string();
+        assert!(message.starts_with("found 11 problems in the criteria:"));
+        assert!(message.contains("row 1, column `q`: q (5) must not exceed p (1) for `linear`"));
+        assert!(message.contains("row 3, column `name`: `cost` is already defined in row 1"));
+        assert!(report.into_result().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_columns() -> Result<()> {
+        let df = df!("name"=> &["cost"], "weight" => &[0.])?;
+        let report = validate_criteria(&df, None);
+        let messages: Vec<String> = report.issues.iter().map(|i| i.to_string()).collect();
+        assert_eq!(
+            vec![
+                "column `criteria_type`: required column is missing",
+                "column `pref_function`: required column is missing",
+                "column `q`: required column is missing",
+                "column `p`: required column is missing",
+                "column `weight`: the weights are all 0",
+            ],
+            messages
+        );
+        Ok(())
+    }
+}
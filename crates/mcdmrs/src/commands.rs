@@ -0,0 +1,635 @@
+use super::output::{configure_the_environment, results_frame, write_results, Format, OutputArgs};
+use clap::{Args, ValueEnum};
+use mcdmrs::prom::{
+    compare::{compare as compare_rankings, scores_from_ranks},
+    df_from_file,
+    interop::columnar::{column_names, prom_from_file},
+    math::rank,
+    thresholds::suggest_thresholds,
+    types::{Fl, MCDMRSError, Result},
+    validate_criteria, CsvOptions, FileFormat, FromConfig, FromPolars, MissingPolicy,
+    ProjectConfig, Prom,
+};
+use ndarray::Array1;
+use polars::prelude::{DataFrame, DataType, NamedFrom, Series};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+#[derive(Debug, Args)]
+pub struct InputArgs {
+    /// The path to the alternatives file: csv (.csv, .txt, .tsv or no extension), Arrow
+    /// IPC (.arrow, .ipc, .feather), Parquet (.parquet, .pq), newline-delimited JSON
+    /// (.ndjson, .jsonl) or XLSX (.xlsx). The peak memory reported next to the
+    /// calculation time is only available on Linux
+    #[arg(short, long)]
+    pub alternatives: PathBuf,
+
+    /// The path to the criteria file, in any of the alternatives formats
+    #[arg(short, long)]
+    pub criteria: PathBuf,
+
+    /// The field delimiter of csv files, by default a tab for .tsv files and `,`
+    /// otherwise. The csv options apply to both files
+    #[arg(long)]
+    pub delimiter: Option<char>,
+
+    /// Comma separated values read as missing in csv files, in addition to empty fields
+    #[arg(long, value_delimiter = ',')]
+    pub null_values: Vec<String>,
+
+    /// Read numbers written with a decimal comma, e.g. `1,5`, in csv files
+    #[arg(long)]
+    pub decimal_comma: bool,
+
+    /// The alternatives column holding a label for each alternative
+    #[arg(long)]
+    pub id: Option<String>,
+
+    /// How missing values are handled: skip, valid, mean, median, worst, best or exclude
+    #[arg(long, default_value = "skip")]
+    pub missing: MissingPolicy,
+
+    /// Write only the `--id` column next to the results, without the other columns of
+    /// the alternatives. Of Arrow IPC and Parquet files only the criteria and `--id`
+    /// columns are then read
+    #[arg(long)]
+    pub results_only: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct PromArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    /// Include the per-criterion unicriterion net flows in the results
+    #[arg(long)]
+    pub include_flows: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RunArgs {
+    /// The path to the project file
+    pub project: PathBuf,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    /// Include the per-criterion unicriterion net flows in the results
+    #[arg(long)]
+    pub include_flows: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct Prom1Args {
+    #[command(flatten)]
+    pub prom: PromArgs,
+
+    /// The path to write the outranking graph to as a csv edge list.
+    /// The graph is printed to stdout if omitted.
+    #[arg(long)]
+    pub graph: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct SensitivityArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    /// The smallest multiple of each criterion weight to evaluate
+    #[arg(long, default_value_t = 0.0)]
+    pub min: Fl,
+
+    /// The largest multiple of each criterion weight to evaluate
+    #[arg(long, default_value_t = 2.0)]
+    pub max: Fl,
+
+    /// The number of evenly spaced weight multiples to evaluate per criterion
+    #[arg(long, default_value_t = 5)]
+    pub steps: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct ExplainArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    /// The number of positive and negative criterion contributions to list per alternative
+    #[arg(long, default_value_t = 3)]
+    pub top: usize,
+
+    /// Explain the preference of one alternative over another instead, given by label
+    /// (see `--id`) or index
+    #[arg(long, num_args = 2, value_names = ["A", "B"])]
+    pub pair: Option<Vec<String>>,
+}
+
+#[derive(Debug, Args)]
+pub struct ThresholdArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    /// The quantile of pairwise differences to suggest as the indifference threshold `q`
+    #[arg(long, default_value_t = 0.1)]
+    pub q_quantile: Fl,
+
+    /// The quantile of pairwise differences to suggest as the preference threshold `p`
+    #[arg(long, default_value_t = 0.5)]
+    pub p_quantile: Fl,
+}
+
+#[derive(Debug, Args)]
+pub struct CompareArgs {
+    /// The path to the reference results file, in any of the alternatives formats
+    pub reference: PathBuf,
+
+    /// The path to the results file compared with the reference
+    pub other: PathBuf,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    /// The column compared in both files, higher is better
+    #[arg(long, default_value = "score")]
+    pub column: String,
+
+    /// Read `--column` as ranks, where 1 is best, instead of scores
+    #[arg(long)]
+    pub ranks: bool,
+
+    /// The column labeling the alternatives in both files, used to match their rows.
+    /// Rows are matched by position if omitted
+    #[arg(long)]
+    pub id: Option<String>,
+
+    /// The number of best alternatives compared by the top-k overlap
+    #[arg(long, default_value_t = 10)]
+    pub top_k: usize,
+}
+
+/// Warns about every criterion with missing values.
+fn report_missing(p: &Prom) {
+    for (j, count) in p.missing_counts().iter().enumerate() {
+        if *count > 0 {
+            eprintln!(
+                "warning: criterion `{}` has {} missing values (policy: {})",
+                p.criterion_name(j),
+                count,
+                p.missing
+            );
+        }
+    }
+}
+
+impl InputArgs {
+    fn csv_options(&self) -> Result<CsvOptions> {
+        if let Some(d) = self.delimiter.filter(|d| !d.is_ascii()) {
+            return Err(MCDMRSError::Error(format!(
+                "the delimiter must be an ascii character, got {:?}",
+                d
+            ))
+            .into());
+        }
+        Ok(CsvOptions {
+            delimiter: self.delimiter.map(|d| d as u8),
+            null_values: self.null_values.clone(),
+            decimal_comma: self.decimal_comma,
+        })
+    }
+}
+
+pub struct Inputs {
+    pub data_df: DataFrame,
+    pub criteria_df: DataFrame,
+    pub prom: Prom,
+}
+
+pub fn load(args: &InputArgs) -> Result<Inputs> {
+    let csv = args.csv_options()?;
+    let criteria_df = df_from_file(&args.criteria, &csv)
+        .map_err(|e| MCDMRSError::Error(format!("failed to load criteria: {:#}", e)))?;
+
+    let columnar = FileFormat::from_path(&args.alternatives)?
+        .columnar()
+        .filter(|_| args.results_only);
+    let (data_df, prom) = match columnar {
+        Some(format) => {
+            let columns = column_names(&args.alternatives, format)?;
+            let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+            validate_criteria(&criteria_df, Some(&columns)).into_result()?;
+
+            let prom =
+                prom_from_file(&args.alternatives, format, &criteria_df, args.id.as_deref())?;
+            let data_df = match (&args.id, &prom.labels) {
+                (Some(id), Some(labels)) => DataFrame::new(vec![Series::new(id, labels.to_vec())])?,
+                _ => DataFrame::default(),
+            };
+            (data_df, prom)
+        }
+        None => {
+            let data_df = df_from_file(&args.alternatives, &csv)
+                .map_err(|e| MCDMRSError::Error(format!("failed to load alternatives: {:#}", e)))?;
+            validate_criteria(&criteria_df, Some(&data_df.get_column_names())).into_result()?;
+
+            let prom = match &args.id {
+                Some(id) => Prom::from_polars_with_labels(&data_df, &criteria_df, id)?,
+                None => Prom::from_polars(&data_df, &criteria_df)?,
+            };
+            let data_df = match (&args.id, args.results_only) {
+                (Some(id), true) => data_df.select([id])?,
+                (None, true) => DataFrame::default(),
+                (_, false) => data_df,
+            };
+            (data_df, prom)
+        }
+    };
+    let prom = prom.with_missing_policy(args.missing);
+    report_missing(&prom);
+
+    Ok(Inputs {
+        data_df,
+        criteria_df,
+        prom,
+    })
+}
+
+/// Returns the peak resident set size of the process in MB. Only Linux reports it,
+/// through `/proc`, so this is `None` elsewhere.
+fn peak_memory_mb() -> Option<f64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kb: f64 = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kb / 1024.0)
+}
+
+fn compute(inputs: &mut Inputs, format: Format) -> Result<()> {
+    let now: Instant = Instant::now();
+    inputs.prom.compute_prom_ii()?;
+    let timing = now.elapsed().as_secs_f64();
+
+    let mut report = format!("Calculation time (ms):  {:.2}", timing * 1000.0);
+    if let Some(mb) = peak_memory_mb() {
+        report += &format!("\nPeak memory (MB):       {:.1}", mb);
+    }
+
+    if format == Format::Table {
+        configure_the_environment(None);
+        println!("{}", report);
+        println!("Scoring Criteria {:#?}", inputs.criteria_df);
+    } else {
+        eprintln!("{}", report);
+    }
+    Ok(())
+}
+
+fn write_prom2(inputs: &mut Inputs, output: &OutputArgs, include_flows: bool) -> Result<()> {
+    compute(inputs, output.format())?;
+
+    let mut results = results_frame(&inputs.data_df, &inputs.prom, include_flows)?;
+    if output.format() == Format::Table && output.output.is_none() {
+        results = results.sort(["score"], true, false)?;
+    }
+
+    output.write(&mut results, "Data with Prom II Scores")
+}
+
+pub fn prom2(args: &PromArgs) -> Result<()> {
+    let mut inputs = load(&args.input)?;
+    write_prom2(&mut inputs, &args.output, args.include_flows)
+}
+
+pub fn run(args: &RunArgs) -> Result<()> {
+    let config = ProjectConfig::from_path(&args.project)?;
+    let data_df = config.load_data()?;
+    let criteria_df = config.criteria_df()?;
+    let prom = Prom::from_config_with_data(&config, &data_df)?;
+    report_missing(&prom);
+
+    let format = match (&args.output.format, &config.output.format) {
+        (Some(f), _) => Some(*f),
+        (None, Some(f)) => Some(
+            Format::from_str(f, true)
+                .map_err(|e| MCDMRSError::Error(format!("invalid output format: {}", e)))?,
+        ),
+        (None, None) => None,
+    };
+    let output = OutputArgs {
+        output: args.output.output.clone().or(config
+            .output
+            .path
+            .as_ref()
+            .map(|p| config.resolve(p))),
+        format,
+    };
+
+    let mut inputs = Inputs {
+        data_df,
+        criteria_df,
+        prom,
+    };
+    write_prom2(
+        &mut inputs,
+        &output,
+        args.include_flows || config.output.include_flows,
+    )
+}
+
+pub fn prom1(args: &Prom1Args) -> Result<()> {
+    let mut inputs = load(&args.prom.input)?;
+    compute(&mut inputs, args.prom.output.format())?;
+
+    let mut results = results_frame(&inputs.data_df, &inputs.prom, args.prom.include_flows)?;
+    if args.prom.output.format() == Format::Table && args.prom.output.output.is_none() {
+        results = results.sort(["phi_plus"], true, false)?;
+    }
+    args.prom
+        .output
+        .write(&mut results, "Data with Prom I Flows")?;
+
+    let (source, target): (Vec<String>, Vec<String>) =
+        inputs.prom.labeled_edges()?.into_iter().unzip();
+    let mut graph = DataFrame::new(vec![
+        Series::new("source", source),
+        Series::new("target", target),
+    ])?;
+
+    match &args.graph {
+        Some(path) => write_results(
+            &mut graph,
+            Some(path),
+            Format::from_path(path).unwrap_or(Format::Csv),
+            "",
+        ),
+        None if args.prom.output.format() == Format::Table => {
+            write_results(&mut graph, None, Format::Table, "Outranking Graph")
+        }
+        None => Ok(()),
+    }
+}
+
+pub fn sensitivity(args: &SensitivityArgs) -> Result<()> {
+    if args.steps < 2 || args.min > args.max || args.min < 0.0 {
+        return Err(MCDMRSError::Error(
+            "Sensitivity requires `--steps` >= 2 and 0 <= `--min` <= `--max`".to_string(),
+        )
+        .into());
+    }
+    let mut inputs = load(&args.input)?;
+    compute(&mut inputs, args.output.format())?;
+
+    let p = &mut inputs.prom;
+    let baseline = rank(
+        p.prom_ii
+            .as_ref()
+            .ok_or(MCDMRSError::Error(
+                "Could not calculate Prom II".to_string(),
+            ))?
+            .score
+            .view(),
+    );
+    let multiples = Array1::<Fl>::linspace(args.min, args.max, args.steps);
+    let base_weight = p.criteria.weight.clone();
+
+    let (mut criterion, mut multiple, mut weight) = (vec![], vec![], vec![]);
+    let (mut best, mut rank_changes, mut max_rank_shift) = (vec![], vec![], vec![]);
+
+    for (j, name) in p.criterion_names().iter().enumerate() {
+        let weights = &multiples * base_weight[j];
+        let sweep = p.weight_sweep(j, weights.view())?;
+
+        for (i, score) in sweep.score.rows().into_iter().enumerate() {
+            let ranks = rank(score);
+            let shifts: Vec<usize> = ranks
+                .iter()
+                .zip(baseline.iter())
+                .map(|(a, b)| a.abs_diff(*b))
+                .collect();
+
+            criterion.push(name.clone());
+            multiple.push(multiples[i]);
+            weight.push(weights[i]);
+            best.push(p.label(ranks.iter().position(|&r| r == 1).unwrap_or(0)));
+            rank_changes.push(shifts.iter().filter(|&&s| s > 0).count() as u32);
+            max_rank_shift.push(shifts.iter().copied().max().unwrap_or(0) as u32);
+        }
+    }
+
+    let mut results = DataFrame::new(vec![
+        Series::new("criterion", criterion),
+        Series::new("multiple", multiple),
+        Series::new("weight", weight),
+        Series::new("best", best),
+        Series::new("rank_changes", rank_changes),
+        Series::new("max_rank_shift", max_rank_shift),
+    ])?;
+
+    args.output.write(&mut results, "Weight Sensitivity")
+}
+
+pub fn explain(args: &ExplainArgs) -> Result<()> {
+    let mut inputs = load(&args.input)?;
+    compute(&mut inputs, args.output.format())?;
+    let p = &inputs.prom;
+
+    if let Some(pair) = &args.pair {
+        let e = p.explain_pair(
+            p.alternative_index(&pair[0])?,
+            p.alternative_index(&pair[1])?,
+        )?;
+        let c = &e.criteria;
+        let mut results = DataFrame::new(vec![
+            Series::new(
+                "criterion",
+                c.iter().map(|c| c.name.clone()).collect::<Vec<_>>(),
+            ),
+            Series::new("weight", c.iter().map(|c| c.weight).collect::<Vec<_>>()),
+            Series::new("a_over_b", c.iter().map(|c| c.a_over_b).collect::<Vec<_>>()),
+            Series::new("b_over_a", c.iter().map(|c| c.b_over_a).collect::<Vec<_>>()),
+            Series::new(
+                "contribution",
+                c.iter().map(|c| c.value).collect::<Vec<_>>(),
+            ),
+        ])?;
+        let title = format!(
+            "{} over {}: pi(a, b) = {:.4}, pi(b, a) = {:.4}",
+            e.a, e.b, e.pi_ab, e.pi_ba
+        );
+        return args.output.write(&mut results, &title);
+    }
+
+    let (mut alternative, mut score, mut rank) = (vec![], vec![], vec![]);
+    let (mut direction, mut criterion, mut contribution) = (vec![], vec![], vec![]);
+    for e in p.explain_all(args.top)? {
+        let signed = [("positive", &e.positive), ("negative", &e.negative)];
+        for (d, contributions) in signed {
+            for c in contributions {
+                alternative.push(e.label.clone());
+                score.push(e.score);
+                rank.push(e.rank as u32);
+                direction.push(d);
+                criterion.push(c.name.clone());
+                contribution.push(c.value);
+            }
+        }
+    }
+
+    let mut results = DataFrame::new(vec![
+        Series::new("alternative", alternative),
+        Series::new("score", score),
+        Series::new("rank", rank),
+        Series::new("direction", direction),
+        Series::new("criterion", criterion),
+        Series::new("contribution", contribution),
+    ])?;
+    args.output.write(&mut results, "Score Contributions")
+}
+
+pub fn thresholds(args: &ThresholdArgs) -> Result<()> {
+    let inputs = load(&args.input)?;
+    let suggestion = suggest_thresholds(
+        inputs.prom.matrix_t.view(),
+        args.q_quantile,
+        args.p_quantile,
+    )?;
+
+    let mut results = DataFrame::new(vec![
+        Series::new("name", inputs.prom.criterion_names().to_vec()),
+        Series::new("pref_function", inputs.prom.criteria.pref_function.to_vec()),
+        Series::new("q", inputs.prom.criteria.q.to_vec()),
+        Series::new("p", inputs.prom.criteria.p.to_vec()),
+        Series::new("suggested_q", suggestion.q.to_vec()),
+        Series::new("suggested_p", suggestion.p.to_vec()),
+    ])?;
+
+    args.output.write(&mut results, "Suggested Thresholds")
+}
+
+/// Loading validates the criteria, reporting every problem at once.
+pub fn validate(args: &InputArgs) -> Result<()> {
+    let inputs = load(args)?;
+
+    let (m, n) = inputs.prom.matrix_t.dim();
+    println!("OK: {} alternatives, {} criteria", n, m);
+    Ok(())
+}
+
+/// Reads `column` of a results file as scores, with the rows in the order of `order`
+/// labels when given.
+fn read_scores(
+    path: &Path,
+    column: &str,
+    id: Option<&str>,
+    order: Option<&[String]>,
+) -> Result<(Array1<Fl>, Vec<String>)> {
+    let df = df_from_file(path, &CsvOptions::default())
+        .map_err(|e| MCDMRSError::Error(format!("failed to load {}: {:#}", path.display(), e)))?;
+    let values: Vec<Fl> = df
+        .column(column)?
+        .cast(&DataType::Float32)?
+        .f32()?
+        .into_iter()
+        .map(|v| v.unwrap_or(Fl::NAN))
+        .collect();
+    let labels: Vec<String> = match id {
+        Some(id) => df
+            .column(id)?
+            .cast(&DataType::String)?
+            .str()?
+            .into_iter()
+            .map(|v| v.unwrap_or_default().to_string())
+            .collect(),
+        None => (0..values.len()).map(|i| i.to_string()).collect(),
+    };
+
+    let index: HashMap<&str, usize> = labels
+        .iter()
+        .enumerate()
+        .map(|(i, l)| (l.as_str(), i))
+        .collect();
+    if index.len() != labels.len() {
+        return Err(
+            MCDMRSError::Error(format!("the labels of {} are not unique", path.display())).into(),
+        );
+    }
+    let scores = match order {
+        Some(order) => {
+            if order.len() != labels.len() {
+                return Err(MCDMRSError::Error(format!(
+                    "{} has {} alternatives, expected {}",
+                    path.display(),
+                    labels.len(),
+                    order.len()
+                ))
+                .into());
+            }
+            order
+                .iter()
+                .map(|l| {
+                    index.get(l.as_str()).map(|&i| values[i]).ok_or_else(|| {
+                        MCDMRSError::Error(format!("`{}` is missing from {}", l, path.display()))
+                    })
+                })
+                .collect::<std::result::Result<Array1<Fl>, _>>()?
+        }
+        None => Array1::from_vec(values),
+    };
+    Ok((scores, labels))
+}
+
+pub fn compare(args: &CompareArgs) -> Result<()> {
+    let id = args.id.as_deref();
+    let (reference, labels) = read_scores(&args.reference, &args.column, id, None)?;
+    let (other, _) = read_scores(&args.other, &args.column, id, Some(&labels))?;
+    let (reference, other) = if args.ranks {
+        (
+            scores_from_ranks(reference.view()),
+            scores_from_ranks(other.view()),
+        )
+    } else {
+        (reference, other)
+    };
+
+    let c = compare_rankings(reference.view(), other.view(), args.top_k)?;
+    let mut results = DataFrame::new(vec![
+        Series::new(
+            "metric",
+            [
+                "spearman",
+                "kendall_tau_b",
+                "weighted_spearman",
+                "ws",
+                "top_k_overlap",
+            ],
+        ),
+        Series::new(
+            "value",
+            [
+                c.spearman,
+                c.kendall_tau_b,
+                c.weighted_spearman,
+                c.ws,
+                c.top_k_overlap,
+            ],
+        ),
+    ])?;
+    let title = format!("Ranking Comparison (top {})", c.k);
+    args.output.write(&mut results, &title)
+}
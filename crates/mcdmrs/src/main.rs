@@ -1,73 +1,62 @@
 #[cfg(all(feature = "io", feature = "cli"))]
-fn run_cli() {
-    use clap::Parser;
-    use mcdmrs::prom::{df_from_csv, FromPolars, Prom};
-    use polars::prelude::{DataFrame, NamedFrom, Series};
-    use std::path::PathBuf;
-    use std::time::Instant;
-
-    fn configure_the_environment() {
-        use std::env;
+mod commands;
+#[cfg(all(feature = "io", feature = "cli"))]
+mod output;
 
-        env::set_var("POLARS_FMT_TABLE_ROUNDED_CORNERS", "1"); // apply rounded corners to UTF8-styled tables.
-        env::set_var("POLARS_FMT_MAX_COLS", "20"); // maximum number of columns shown when formatting DataFrames.
-        env::set_var("POLARS_FMT_MAX_ROWS", "20"); // maximum number of rows shown when formatting DataFrames.
-        env::set_var("POLARS_FMT_STR_LEN", "50"); // maximum number of characters printed per string value.
-    }
+#[cfg(all(feature = "io", feature = "cli"))]
+fn run_cli() -> mcdmrs::prom::Result<()> {
+    use clap::{Parser, Subcommand};
+    use commands::{
+        CompareArgs, ExplainArgs, InputArgs, Prom1Args, PromArgs, RunArgs, SensitivityArgs,
+        ThresholdArgs,
+    };
 
     #[derive(Debug, Parser)]
     #[command(author, version, about, long_about = None, arg_required_else_help = true)]
     struct Cli {
-        /// The path to the alternatives file
-        #[arg(short, long)]
-        alternatives: PathBuf,
-
-        /// The path to the criteria file
-        #[arg(short, long)]
-        criteria: PathBuf,
+        #[command(subcommand)]
+        command: Command,
     }
 
-    let args: Cli = Cli::parse();
-
-    let mut data_df = df_from_csv(
-        args.alternatives
-            .to_str()
-            .expect("failed to convert data path to str"),
-    )
-    .expect("failed to load data");
-
-    let criteria_df: DataFrame = df_from_csv(
-        args.criteria
-            .to_str()
-            .expect("failed to convert criteria path to str"),
-    )
-    .expect("failed to load criteria");
-
-    let mut p: Prom = Prom::from_polars(&data_df, &criteria_df).unwrap();
-    let now: Instant = Instant::now();
-    p.compute_prom_ii().expect("failed to compute prom.");
-    let timing = now.elapsed().as_secs_f64();
-
-    let pii = p.prom_ii.unwrap();
-
-    let score = Series::new("score", pii.score.to_vec());
-    let normalized_score = Series::new("normalized_score", pii.normalized_score.to_vec());
-    data_df.with_column(score).unwrap();
-    data_df.with_column(normalized_score).unwrap();
+    #[derive(Debug, Subcommand)]
+    enum Command {
+        /// Compare the rankings of two results files
+        Compare(CompareArgs),
+        /// List the criteria driving each Promethee II score, or one pairwise preference
+        Explain(ExplainArgs),
+        /// Promethee I partial ranking and outranking graph
+        Prom1(Prom1Args),
+        /// Promethee II complete ranking
+        Prom2(PromArgs),
+        /// Promethee II complete ranking of a TOML project file
+        Run(RunArgs),
+        /// Re-run Promethee II while sweeping each criterion weight
+        Sensitivity(SensitivityArgs),
+        /// Suggest q and p thresholds from the spread of the alternatives
+        SuggestThresholds(ThresholdArgs),
+        /// Check the criteria file against the alternatives without computing
+        Validate(InputArgs),
+    }
 
-    configure_the_environment();
-    println!("Calculation time (ms):  {:.2}", timing * 1000.0);
-    println!("Scoring Criteria {:#?}", criteria_df);
-    println!(
-        "Data with Prom II Scores {:#?}",
-        data_df.sort(["score"], true, false).unwrap()
-    );
+    match Cli::parse().command {
+        Command::Compare(args) => commands::compare(&args),
+        Command::Explain(args) => commands::explain(&args),
+        Command::Prom1(args) => commands::prom1(&args),
+        Command::Prom2(args) => commands::prom2(&args),
+        Command::Run(args) => commands::run(&args),
+        Command::Sensitivity(args) => commands::sensitivity(&args),
+        Command::SuggestThresholds(args) => commands::thresholds(&args),
+        Command::Validate(args) => commands::validate(&args),
+    }
 }
 
 fn main() {
     if cfg!(feature = "io") && cfg!(feature = "cli") {
         #[cfg(all(feature = "io", feature = "cli"))]
-        run_cli();
+        if let Err(err) = run_cli() {
+            eprintln!("{:#}", err);
+            std::process::exit(1);
+        }
     } else {
         println!("`cli` and `io` feature are required for command line use.");
     }
@@ -0,0 +1,142 @@
+use clap::{Args, ValueEnum};
+use mcdmrs::prom::{
+    types::{MCDMRSError, Result},
+    Prom,
+};
+use polars::prelude::{
+    CsvWriter, DataFrame, JsonFormat, JsonWriter, NamedFrom, ParquetWriter, SerWriter, Series,
+};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Csv,
+    Json,
+    Parquet,
+    Table,
+}
+
+impl Format {
+    /// Infers the output format from the file extension of `path`.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "csv" => Some(Format::Csv),
+            "json" => Some(Format::Json),
+            "parquet" | "pq" => Some(Format::Parquet),
+            "txt" => Some(Format::Table),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct OutputArgs {
+    /// The path to write results to. Results are printed to stdout if omitted.
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// The results format. Inferred from the `--output` extension if omitted.
+    #[arg(short, long, value_enum)]
+    pub format: Option<Format>,
+}
+
+impl OutputArgs {
+    pub fn format(&self) -> Format {
+        self.format
+            .or_else(|| self.output.as_deref().and_then(Format::from_path))
+            .unwrap_or(Format::Table)
+    }
+
+    pub fn write(&self, df: &mut DataFrame, title: &str) -> Result<()> {
+        write_results(df, self.output.as_deref(), self.format(), title)
+    }
+}
+
+pub fn configure_the_environment(max_rows: Option<usize>) {
+    use std::env;
+
+    let max_rows = max_rows.map_or("20".to_string(), |n| n.to_string());
+
+    env::set_var("POLARS_FMT_TABLE_ROUNDED_CORNERS", "1"); // apply rounded corners to UTF8-styled tables.
+    env::set_var("POLARS_FMT_MAX_COLS", "20"); // maximum number of columns shown when formatting DataFrames.
+    env::set_var("POLARS_FMT_MAX_ROWS", max_rows); // maximum number of rows shown when formatting DataFrames.
+    env::set_var("POLARS_FMT_STR_LEN", "50"); // maximum number of characters printed per string value.
+}
+
+/// Appends the Promethee results to a copy of the alternatives frame.
+///
+/// Adds `phi_plus`, `phi_minus`, `score`, `normalized_score` and `rank` columns, and
+/// when `include_flows` is set, one `<criterion>_flow` column per criterion holding
+/// its (unweighted) unicriterion net flow.
+pub fn results_frame(data_df: &DataFrame, p: &Prom, include_flows: bool) -> Result<DataFrame> {
+    let (mc, pi, pii) = match (&p.mc_flow, &p.prom_i, &p.prom_ii) {
+        (Some(mc), Some(pi), Some(pii)) => (mc, pi, pii),
+        _ => {
+            return Err(
+                MCDMRSError::Error("Prom II must be computed before export".to_string()).into(),
+            )
+        }
+    };
+
+    let mut df = data_df.clone();
+    df.with_column(Series::new("phi_plus", pi.phi_plus_score.to_vec()))?;
+    df.with_column(Series::new("phi_minus", pi.phi_minus_score.to_vec()))?;
+    df.with_column(Series::new("score", pii.score.to_vec()))?;
+    df.with_column(Series::new(
+        "normalized_score",
+        pii.normalized_score.to_vec(),
+    ))?;
+    df.with_column(Series::new(
+        "rank",
+        pii.ranks().iter().map(|&r| r as u32).collect::<Vec<u32>>(),
+    ))?;
+
+    if include_flows {
+        let flow = &mc.pref_matrix_plus_t - &mc.pref_matrix_minus_t;
+        for (name, row) in p.criterion_names().iter().zip(flow.rows()) {
+            df.with_column(Series::new(&format!("{}_flow", name), row.to_vec()))?;
+        }
+    }
+
+    Ok(df)
+}
+
+/// Writes `df` to `path` in the requested format, or to stdout when no path is given.
+pub fn write_results(
+    df: &mut DataFrame,
+    path: Option<&Path>,
+    format: Format,
+    title: &str,
+) -> Result<()> {
+    match (format, path) {
+        (Format::Csv, Some(path)) => CsvWriter::new(File::create(path)?).finish(df)?,
+        (Format::Csv, None) => CsvWriter::new(std::io::stdout()).finish(df)?,
+        (Format::Json, Some(path)) => JsonWriter::new(File::create(path)?)
+            .with_json_format(JsonFormat::Json)
+            .finish(df)?,
+        (Format::Json, None) => JsonWriter::new(std::io::stdout())
+            .with_json_format(JsonFormat::Json)
+            .finish(df)?,
+        (Format::Parquet, Some(path)) => {
+            ParquetWriter::new(File::create(path)?).finish(df)?;
+        }
+        (Format::Parquet, None) => {
+            return Err(MCDMRSError::Error(
+                "parquet output requires an `--output` path".to_string(),
+            )
+            .into())
+        }
+        (Format::Table, Some(path)) => {
+            configure_the_environment(Some(df.height()));
+            writeln!(File::create(path)?, "{}", df)?;
+        }
+        (Format::Table, None) => {
+            configure_the_environment(None);
+            println!("{} {:#?}", title, df);
+        }
+    }
+
+    Ok(())
+}
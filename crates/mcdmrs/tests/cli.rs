@@ -5,7 +5,8 @@ use std::process::Command; // Run programs // Add methods on commands
 fn test_happy_path() {
     let mut cmd = Command::cargo_bin("mcdmrs").expect("executable not found");
 
-    cmd.arg("--alternatives")
+    cmd.arg("prom2")
+        .arg("--alternatives")
         .arg("../../examples/data/alternatives.csv")
         .arg("--criteria")
         .arg("../../examples/data/criteria.csv");
@@ -17,7 +18,8 @@ fn test_happy_path() {
 fn test_file_doesnt_exist_buffer() -> Result<(), Box<dyn std::error::Error>> {
     let mut cmd = Command::cargo_bin("mcdmrs")?;
 
-    cmd.arg("--alternatives")
+    cmd.arg("prom2")
+        .arg("--alternatives")
         .arg("test/file/doesnt/exist")
         .arg("--criteria")
         .arg("test/file/doesnt/exist");
@@ -26,3 +28,580 @@ fn test_file_doesnt_exist_buffer() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[test]
+fn test_output_csv() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join(format!("mcdmrs_test_output_{}.csv", std::process::id()));
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("prom2")
+        .arg("--alternatives")
+        .arg("../../examples/data/alternatives.csv")
+        .arg("--criteria")
+        .arg("../../examples/data/criteria.csv")
+        .arg("--output")
+        .arg(&path)
+        .arg("--include-flows");
+
+    cmd.assert().success();
+
+    let contents = std::fs::read_to_string(&path)?;
+    std::fs::remove_file(&path)?;
+    let mut lines = contents.lines();
+    let header: Vec<&str> = lines.next().unwrap_or_default().split(',').collect();
+    assert_eq!(
+        vec![
+            "\"\"",
+            "cost",
+            "treated_area",
+            "site_slope",
+            "site_footprint",
+            "tss_conc_pct_reduction",
+            "site_inequity_factor",
+            "risk_factor",
+            "phi_plus",
+            "phi_minus",
+            "score",
+            "normalized_score",
+            "rank",
+            "cost_flow",
+            "treated_area_flow",
+            "site_slope_flow",
+            "site_footprint_flow",
+            "tss_conc_pct_reduction_flow",
+            "site_inequity_factor_flow",
+            "risk_factor_flow",
+        ],
+        header
+    );
+    let rows: Vec<Vec<&str>> = lines.map(|l| l.split(',').collect()).collect();
+    let column = |j: usize| -> Vec<f64> { rows.iter().map(|r| r[j].parse().unwrap()).collect() };
+
+    let expected = [
+        -0.4666667,
+        0.15625387,
+        -0.15233946,
+        -0.120814025,
+        -0.0462766,
+        -0.14627206,
+        0.4703264,
+        0.0795117,
+        0.31159133,
+        -0.0853146,
+    ];
+    let score = column(10);
+    assert_eq!(expected.len(), score.len());
+    assert!(expected
+        .iter()
+        .zip(&score)
+        .all(|(a, b)| (a - b).abs() < 1e-5));
+    assert_eq!(vec![10., 3., 9., 7., 5., 8., 1., 4., 2., 6.], column(12));
+    // the original columns are written unchanged
+    assert_eq!(274687.28, column(1)[0]);
+
+    Ok(())
+}
+
+#[test]
+fn test_output_json_stdout() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("prom2")
+        .arg("--alternatives")
+        .arg("../../examples/data/alternatives.csv")
+        .arg("--criteria")
+        .arg("../../examples/data/criteria.csv")
+        .arg("--format")
+        .arg("json");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let text = String::from_utf8(output)?;
+    assert!(text.starts_with('[') && text.contains("\"normalized_score\""));
+
+    Ok(())
+}
+
+#[test]
+fn test_output_parquet_requires_path() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("prom2")
+        .arg("--alternatives")
+        .arg("../../examples/data/alternatives.csv")
+        .arg("--criteria")
+        .arg("../../examples/data/criteria.csv")
+        .arg("--format")
+        .arg("parquet");
+
+    cmd.assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn test_requires_subcommand() {
+    let mut cmd = Command::cargo_bin("mcdmrs").expect("executable not found");
+
+    cmd.arg("--alternatives")
+        .arg("../../examples/data/alternatives.csv")
+        .arg("--criteria")
+        .arg("../../examples/data/criteria.csv");
+
+    cmd.assert().failure();
+}
+
+#[test]
+fn test_prom1_graph() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join("mcdmrs_test_graph.csv");
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("prom1")
+        .arg("--alternatives")
+        .arg("../../examples/data/alternatives.csv")
+        .arg("--criteria")
+        .arg("../../examples/data/criteria.csv")
+        .arg("--graph")
+        .arg(&path);
+
+    cmd.assert().success();
+
+    let contents = std::fs::read_to_string(&path)?;
+    assert!(contents.starts_with("source,target"));
+    assert!(contents.lines().count() > 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_id_column() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("sensitivity")
+        .arg("--alternatives")
+        .arg("../../examples/data/alternatives.csv")
+        .arg("--criteria")
+        .arg("../../examples/data/criteria.csv")
+        .arg("--id")
+        .arg("missing_column");
+
+    cmd.assert().failure();
+
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("prom2")
+        .arg("--alternatives")
+        .arg("../../examples/data/alternatives.csv")
+        .arg("--criteria")
+        .arg("../../examples/data/criteria.csv")
+        .arg("--id")
+        .arg("")
+        .arg("--include-flows")
+        .arg("--format")
+        .arg("csv");
+
+    let output = cmd.output()?;
+    assert!(output.status.success());
+    let header = String::from_utf8(output.stdout)?;
+    assert!(header.lines().next().unwrap().contains("cost_flow"));
+
+    Ok(())
+}
+
+#[test]
+fn test_missing_policy() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("prom2")
+        .arg("--alternatives")
+        .arg("../../examples/data/alternatives_nan.csv")
+        .arg("--criteria")
+        .arg("../../examples/data/criteria.csv")
+        .arg("--missing")
+        .arg("valid")
+        .arg("--format")
+        .arg("csv");
+
+    let output = cmd.output()?;
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(
+        stderr.contains("missing values (policy: valid)"),
+        "{stderr}"
+    );
+
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("validate")
+        .arg("--alternatives")
+        .arg("../../examples/data/alternatives.csv")
+        .arg("--criteria")
+        .arg("../../examples/data/criteria.csv")
+        .arg("--missing")
+        .arg("zero");
+
+    cmd.assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn test_explain() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("explain")
+        .arg("--alternatives")
+        .arg("../../examples/data/alternatives.csv")
+        .arg("--criteria")
+        .arg("../../examples/data/criteria.csv")
+        .arg("--top")
+        .arg("1")
+        .arg("--format")
+        .arg("csv");
+
+    let output = cmd.output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.starts_with("alternative,score,rank,direction,criterion,contribution"));
+    // at most one positive and one negative contribution per alternative
+    assert!(stdout.lines().count() <= 1 + 2 * 10);
+
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("explain")
+        .arg("--alternatives")
+        .arg("../../examples/data/alternatives.csv")
+        .arg("--criteria")
+        .arg("../../examples/data/criteria.csv")
+        .arg("--pair")
+        .arg("0")
+        .arg("3")
+        .arg("--format")
+        .arg("csv");
+
+    let output = cmd.output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.starts_with("criterion,weight,a_over_b,b_over_a,contribution"));
+
+    Ok(())
+}
+
+#[test]
+fn test_sensitivity() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("sensitivity")
+        .arg("--alternatives")
+        .arg("../../examples/data/alternatives.csv")
+        .arg("--criteria")
+        .arg("../../examples/data/criteria.csv")
+        .arg("--steps")
+        .arg("3")
+        .arg("--format")
+        .arg("csv");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let text = String::from_utf8(output)?;
+    // header plus 3 steps for each of the 7 criteria
+    assert_eq!(text.lines().count(), 1 + 3 * 7);
+
+    Ok(())
+}
+
+#[test]
+fn test_suggest_thresholds() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("suggest-thresholds")
+        .arg("--alternatives")
+        .arg("../../examples/data/alternatives.csv")
+        .arg("--criteria")
+        .arg("../../examples/data/criteria.csv")
+        .arg("--format")
+        .arg("csv");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let text = String::from_utf8(output)?;
+    assert!(text.starts_with("name,pref_function,q,p,suggested_q,suggested_p"));
+
+    Ok(())
+}
+
+#[test]
+fn test_parquet_alternatives() -> Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join("mcdmrs_test_alternatives.parquet");
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("prom2")
+        .arg("--alternatives")
+        .arg("../../examples/data/alternatives.csv")
+        .arg("--criteria")
+        .arg("../../examples/data/criteria.csv")
+        .arg("--output")
+        .arg(&path);
+
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("prom2")
+        .arg("--alternatives")
+        .arg(&path)
+        .arg("--criteria")
+        .arg("../../examples/data/criteria.csv")
+        .arg("--format")
+        .arg("csv");
+
+    let output = cmd.assert().success().get_output().clone();
+    let text = String::from_utf8(output.stdout)?;
+    // the alternatives' columns are kept, and the results columns are replaced
+    assert_eq!(
+        text.lines().next().unwrap_or_default(),
+        "\"\",cost,treated_area,site_slope,site_footprint,tss_conc_pct_reduction,\
+         site_inequity_factor,risk_factor,phi_plus,phi_minus,score,normalized_score,rank"
+    );
+    assert_eq!(text.lines().count(), 11);
+    assert!(String::from_utf8(output.stderr)?.contains("Peak memory (MB)"));
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn test_results_only() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir();
+    let id = std::process::id();
+    let csv = dir.join(format!("mcdmrs_test_results_only_{}.csv", id));
+    let criteria = dir.join(format!("mcdmrs_test_results_only_criteria_{}.csv", id));
+    let parquet = dir.join(format!("mcdmrs_test_results_only_{}.parquet", id));
+    std::fs::write(&csv, "site,cost,quality\na,1.5,3\nb,2.25,5\nc,0.75,4\n")?;
+    std::fs::write(
+        &criteria,
+        "name,weight,criteria_type,pref_function,q,p\ncost,1,-1,usual,0,0\nquality,1,1,usual,0,0\n",
+    )?;
+
+    let prom2 = |alternatives: &std::path::Path| -> Result<Command, Box<dyn std::error::Error>> {
+        let mut cmd = Command::cargo_bin("mcdmrs")?;
+        cmd.arg("prom2")
+            .arg("--alternatives")
+            .arg(alternatives)
+            .arg("--criteria")
+            .arg(&criteria)
+            .arg("--id")
+            .arg("site");
+        Ok(cmd)
+    };
+    prom2(&csv)?
+        .arg("--output")
+        .arg(&parquet)
+        .assert()
+        .success();
+
+    for alternatives in [&csv, &parquet] {
+        let output = prom2(alternatives)?
+            .arg("--results-only")
+            .arg("--format")
+            .arg("csv")
+            .assert()
+            .success()
+            .get_output()
+            .clone();
+        let text = String::from_utf8(output.stdout)?;
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            vec![
+                "site,phi_plus,phi_minus,score,normalized_score,rank",
+                "a,0.25,0.75,-0.5,0.0,3",
+                "b,0.5,0.5,0.0,0.5,2",
+                "c,0.75,0.25,0.5,1.0,1",
+            ],
+            lines
+        );
+    }
+
+    for path in [csv, criteria, parquet] {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_csv_options() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir();
+    let alternatives = dir.join("mcdmrs_test_semicolon_alternatives.csv");
+    let criteria = dir.join("mcdmrs_test_semicolon_criteria.csv");
+    std::fs::write(
+        &alternatives,
+        "site;cost;quality\na;1,5;3\nb;2,25;-\nc;0,75;4\n",
+    )?;
+    std::fs::write(
+        &criteria,
+        "name;weight;criteria_type;pref_function;q;p\ncost;1;-1;vshape;0;0,5\nquality;1;1;usual;0;0\n",
+    )?;
+
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("prom2")
+        .arg("--alternatives")
+        .arg(&alternatives)
+        .arg("--criteria")
+        .arg(&criteria)
+        .arg("--id")
+        .arg("site")
+        .arg("--delimiter")
+        .arg(";")
+        .arg("--null-values")
+        .arg("-")
+        .arg("--decimal-comma")
+        .arg("--format")
+        .arg("csv");
+
+    let output = cmd.assert().success().get_output().clone();
+    let text = String::from_utf8(output.stdout)?;
+    assert!(text.starts_with("site,cost,quality,phi_plus"));
+    assert!(text.contains("c,0.75,4"));
+    assert!(String::from_utf8(output.stderr)?.contains("`quality` has 1 missing values"));
+
+    // without the csv options the files hold a single column
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("validate")
+        .arg("--alternatives")
+        .arg(&alternatives)
+        .arg("--criteria")
+        .arg(&criteria);
+
+    cmd.assert().failure().code(1);
+
+    Ok(())
+}
+
+#[test]
+fn test_validate() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("validate")
+        .arg("--alternatives")
+        .arg("../../examples/data/alternatives.csv")
+        .arg("--criteria")
+        .arg("../../examples/data/criteria.csv");
+
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("validate")
+        .arg("--alternatives")
+        .arg("../../examples/data/criteria.csv")
+        .arg("--criteria")
+        .arg("../../examples/data/criteria.csv");
+
+    cmd.assert().failure().code(1);
+
+    Ok(())
+}
+
+#[test]
+fn test_validate_report() -> Result<(), Box<dyn std::error::Error>> {
+    let criteria = std::env::temp_dir().join("mcdmrs_test_invalid_criteria.csv");
+    std::fs::write(
+        &criteria,
+        "name,weight,criteria_type,pref_function,q,p\n\
+         cost,-2,-1,linear,20000,100000\n\
+         slope,1,1,gaussian,0,0\n\
+         unknown,1,1,level,5,1\n",
+    )?;
+
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("validate")
+        .arg("--alternatives")
+        .arg("../../examples/data/alternatives.csv")
+        .arg("--criteria")
+        .arg(&criteria);
+
+    let output = cmd.assert().failure().code(1).get_output().clone();
+    let text = String::from_utf8(output.stderr)?;
+    assert!(text.contains("found 5 problems in the criteria"), "{text}");
+    for problem in [
+        "row 1, column `weight`: must not be negative",
+        "row 2, column `name`: `slope` is not a column of the alternatives",
+        "row 2, column `pref_function`: unknown preference function `gaussian`",
+        "row 3, column `name`: `unknown` is not a column of the alternatives",
+        "row 3, column `q`: q (5) must not exceed p (1) for `level`",
+    ] {
+        assert!(text.contains(problem), "missing {problem}: {text}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_run_project() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+
+    cmd.arg("run")
+        .arg("../../examples/data/project.toml")
+        .arg("--format")
+        .arg("csv");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let text = String::from_utf8(output)?;
+    assert!(text.lines().next().unwrap_or_default().contains("score"));
+    assert_eq!(text.lines().count(), 11);
+
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+    cmd.arg("run").arg("test/file/doesnt/exist.toml");
+    cmd.assert().failure().code(1);
+
+    Ok(())
+}
+
+#[test]
+fn test_compare() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir();
+    let reference = dir.join("mcdmrs_test_compare_reference.csv");
+    let other = dir.join("mcdmrs_test_compare_other.csv");
+    std::fs::write(&reference, "id,score\na,3\nb,2\nc,1\nd,0\n")?;
+    // the reverse order, with the rows shuffled
+    std::fs::write(&other, "id,score\nc,2\na,0\nd,3\nb,1\n")?;
+
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+    cmd.arg("compare")
+        .arg(&reference)
+        .arg(&other)
+        .arg("--id")
+        .arg("id")
+        .arg("--top-k")
+        .arg("2")
+        .arg("--format")
+        .arg("csv");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let text = String::from_utf8(output)?;
+    assert!(text.starts_with("metric,value"));
+    assert!(text.contains("spearman,-1.0"));
+    assert!(text.contains("kendall_tau_b,-1.0"));
+    assert!(text.contains("top_k_overlap,0.0"));
+
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+    cmd.arg("compare")
+        .arg(&reference)
+        .arg(&reference)
+        .arg("--ranks")
+        .arg("--format")
+        .arg("csv");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    assert!(String::from_utf8(output)?.contains("ws,1.0"));
+
+    let mut cmd = Command::cargo_bin("mcdmrs")?;
+    cmd.arg("compare")
+        .arg(&reference)
+        .arg(&other)
+        .arg("--column")
+        .arg("rank");
+    cmd.assert().failure();
+
+    Ok(())
+}